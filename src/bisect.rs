@@ -0,0 +1,275 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+};
+
+use clap::Subcommand;
+use serde::{ Serialize, Deserialize };
+use simpleio::{ get_home, file_exists, read_file_into_string };
+
+use crate::{
+    DT, Event, Events, replay, format_dt, fail,
+    colour::colours,
+    output::{ Output, Tabular, print_structured },
+    state::{ parse_date, events_before },
+    rollback::plan,
+};
+
+#[derive(Subcommand, Debug)]
+pub enum BisectStep{
+    #[clap(about = "Start bisecting the upgrades between a good and a bad date.")]
+    Start{
+        #[clap(long, value_parser = parse_date, help = "A date when everything still worked.")]
+        good: DT,
+        #[clap(long, value_parser = parse_date, help = "A date when it was broken.")]
+        bad: DT,
+    },
+    #[clap(about = "The versions of this step work.")]
+    Good,
+    #[clap(about = "The versions of this step are broken.")]
+    Bad,
+    #[clap(about = "Show the current step again.")]
+    Show,
+    #[clap(about = "Stop bisecting and forget the state.")]
+    Reset,
+}
+
+/// What is kept between invocations, the ends are amounts of events from the start of the log.
+/// Logs only grow, so these stay valid while bisecting.
+#[derive(Serialize, Deserialize)]
+struct Bisection{
+    // the state after this many events works
+    good: usize,
+    // the state after this many events is broken
+    bad: usize,
+}
+
+/// An upgrade of the transaction that broke things, with the versions before and after.
+#[derive(Serialize)]
+struct Culprit<'a>{
+    package: &'a str,
+    old_version: Option<&'a str>,
+    new_version: Option<&'a str>,
+}
+
+impl Tabular for Culprit<'_>{
+    const HEADER: &'static [&'static str] = &["package", "old_version", "new_version"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            self.old_version.unwrap_or("").to_string(),
+            self.new_version.unwrap_or("").to_string(),
+        ]
+    }
+}
+
+fn state_path() -> PathBuf{
+    let mut path = match get_home(){
+        Ok(home) => home,
+        Err(e) => panic!("Error: could not find the home directory: {}", e),
+    };
+    path.push(".cache");
+    path.push("paclog");
+    path.push("bisect.json");
+    path
+}
+
+fn load(path: &PathBuf) -> Bisection{
+    if !file_exists(path) {
+        fail("not bisecting, start with 'paclog bisect start --good DATE --bad DATE'!");
+    }
+    let string = match read_file_into_string(path){
+        Ok(string) => string,
+        Err(e) => panic!("Error: could not read '{}': {}", path.display(), e),
+    };
+    match serde_json::from_str(&string){
+        Ok(bisection) => bisection,
+        Err(e) => panic!("Error: could not parse '{}': {}", path.display(), e),
+    }
+}
+
+fn save(path: &PathBuf, bisection: &Bisection){
+    let written = path.parent().map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, serde_json::to_string(bisection).unwrap_or_default()));
+    if let Err(e) = written {
+        panic!("Error: could not write '{}': {}", path.display(), e);
+    }
+}
+
+/// The transactions that upgraded or downgraded something between the ends, as ranges of events.
+/// A range ends where the next transaction starts, or at the bad end.
+fn candidates(events: &Events, bisection: &Bisection) -> Vec<(usize, usize)>{
+    let starts = events.iter().enumerate()
+        .filter(|(_, e)| matches!(e, Event::Command(_, _)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    starts.iter().enumerate()
+        .filter(|(_, start)| (bisection.good..bisection.bad).contains(*start))
+        .map(|(n, start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(events.len()).min(bisection.bad);
+            (*start, end)
+        })
+        .filter(|(start, end)| events[*start..*end].iter()
+            .any(|e| matches!(e, Event::Upgraded(_, _, _) | Event::Downgraded(_, _, _))))
+        .collect()
+}
+
+/// The transaction whose versions get tested, the middle one, rounded down.
+fn middle(candidates: &[(usize, usize)]) -> (usize, usize){
+    candidates[(candidates.len() - 1) / 2]
+}
+
+impl Bisection{
+    /// Moves one end to right after the middle transaction, once it was tested good or bad.
+    fn mark(&mut self, events: &Events, good: bool){
+        let candidates = candidates(events, self);
+        if candidates.len() < 2 { return; }
+        let (_, end) = middle(&candidates);
+        if good { self.good = end; } else { self.bad = end; }
+    }
+}
+
+/// Halves the upgrade transactions between a date that worked and one that did not, like
+/// `git bisect`. Every step prints how to roll back to the versions right after the middle
+/// transaction, which then gets marked good or bad until one transaction is left.
+pub fn bisect(events: Events, step: BisectStep, cache: &str, output: Output){
+    let path = state_path();
+    let mut bisection = match step{
+        BisectStep::Start{ good, bad } => {
+            if good >= bad {
+                fail("--good has to be before --bad!");
+            }
+            Bisection{
+                good: events_before(&events, good).len(),
+                bad: events_before(&events, bad).len(),
+            }
+        },
+        BisectStep::Reset => {
+            match fs::remove_file(&path){
+                Ok(()) => { },
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    fail("not bisecting, nothing to reset!");
+                },
+                Err(e) => fail(format!("could not remove '{}': {}", path.display(), e)),
+            }
+            println!("Stopped bisecting, run 'pacman -Syu' to get back to the latest versions.");
+            return;
+        },
+        _ => load(&path),
+    };
+    if bisection.bad > events.len() {
+        fail("the log is shorter than when bisecting started, run 'paclog bisect reset'!");
+    }
+    match step{
+        BisectStep::Good => bisection.mark(&events, true),
+        BisectStep::Bad => bisection.mark(&events, false),
+        _ => { },
+    }
+    let candidates = candidates(&events, &bisection);
+    if candidates.is_empty() {
+        fail("no upgrades between the good and the bad date!");
+    }
+    save(&path, &bisection);
+
+    let c = colours();
+    if let [(start, end)] = candidates[..] {
+        let (before, after) = (replay(&events[..start]), replay(&events[..end]));
+        let culprits = events[start..end].iter()
+            .filter_map(|e| match e{
+                Event::Upgraded(_, package, _) | Event::Downgraded(_, package, _) => Some(Culprit{
+                    package,
+                    old_version: before.get(package.as_str()).copied(),
+                    new_version: after.get(package.as_str()).copied(),
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if output.structured() {
+            print_structured(output, &culprits, &culprits);
+            return;
+        }
+        if let Event::Command(dt, command) = &events[start] {
+            println!(
+                "The first bad transaction is {} {}{}{}, it changed:",
+                format_dt(*dt), c.command, command, c.reset
+            );
+        }
+        for culprit in &culprits{
+            println!(
+                "\t{}{}{} {}{} -> {}{}",
+                c.package, culprit.package, c.reset, c.version,
+                culprit.old_version.unwrap_or(""), culprit.new_version.unwrap_or(""), c.reset
+            );
+        }
+        println!("Run 'paclog bisect reset' when done.");
+        return;
+    }
+
+    let (start, end) = middle(&candidates);
+    let description = match &events[start]{
+        Event::Command(dt, command) => format!("to after {} '{}'", format_dt(*dt), command),
+        _ => String::from("to the middle"),
+    };
+    if !output.structured() {
+        let steps = usize::BITS - (candidates.len() - 1).leading_zeros();
+        println!(
+            "Bisecting: {}{}{} upgrade transactions left, about {} steps.",
+            c.total, candidates.len(), c.reset, steps
+        );
+    }
+    plan(&events, &events[..end], &description, cache, output);
+    if !output.structured() {
+        println!("\nThen test and run 'paclog bisect good' or 'paclog bisect bad'.");
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// A log with three upgrade transactions and an install in between.
+    fn events() -> Events{
+        let upgrade = |day: u8, package: &str| vec![
+            Event::Command((2024, 1, day, 0), String::from("pacman -Syu")),
+            Event::Upgraded((2024, 1, day, 0), package.to_string(), String::from("(1-1 -> 2-1)")),
+        ];
+        let mut events = upgrade(1, "glibc");
+        events.push(Event::Command((2024, 1, 2, 0), String::from("pacman -S vim")));
+        let vim = (String::from("vim"), String::from("(9.1-1)"));
+        events.push(Event::Installed((2024, 1, 2, 0), vim.0, vim.1));
+        events.extend(upgrade(3, "linux"));
+        events.extend(upgrade(4, "mesa"));
+        events
+    }
+
+    #[test]
+    fn only_upgrade_transactions_are_candidates(){
+        let events = events();
+        let bisection = Bisection{ good: 0, bad: events.len() };
+        assert_eq!(candidates(&events, &bisection), vec![(0, 2), (4, 6), (6, 8)]);
+        assert_eq!(middle(&candidates(&events, &bisection)), (4, 6));
+    }
+
+    #[test]
+    fn bad_then_good_leaves_the_middle_transaction(){
+        let events = events();
+        let mut bisection = Bisection{ good: 0, bad: events.len() };
+        bisection.mark(&events, false);
+        assert_eq!(candidates(&events, &bisection), vec![(0, 2), (4, 6)]);
+        bisection.mark(&events, true);
+        assert_eq!(candidates(&events, &bisection), vec![(4, 6)]);
+        // nothing left to halve
+        bisection.mark(&events, false);
+        assert_eq!(candidates(&events, &bisection), vec![(4, 6)]);
+    }
+
+    #[test]
+    fn good_twice_leaves_the_last_transaction(){
+        let events = events();
+        let mut bisection = Bisection{ good: 0, bad: events.len() };
+        bisection.mark(&events, true);
+        assert_eq!(candidates(&events, &bisection), vec![(6, 8)]);
+    }
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, Marker, hours, format_dt, format_hours,
+    colour::colours,
+    output::{
+        Output, Tabular, Table, Row, print_structured, serialize_dt, serialize_option_dt, iso_dt,
+    },
+    chart::print_bars,
+    warnings::sync_flags,
+};
+
+// upper ends of the interval buckets in days, the last one is open
+const BUCKETS: [(i64, &str); 7] = [
+    (1, "up to 1 day"), (3, "up to 3 days"), (7, "up to 1 week"), (14, "up to 2 weeks"),
+    (30, "up to 1 month"), (90, "up to 3 months"), (i64::MAX / 24, "longer"),
+];
+
+/// The time between two full upgrades.
+#[derive(Serialize, Clone, Copy)]
+struct Interval{
+    #[serde(serialize_with = "serialize_dt")]
+    from: DT,
+    #[serde(serialize_with = "serialize_dt")]
+    to: DT,
+    hours: i64,
+}
+
+impl Tabular for Interval{
+    const HEADER: &'static [&'static str] = &["from", "to", "hours"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![iso_dt(self.from), iso_dt(self.to), self.hours.to_string()]
+    }
+}
+
+#[derive(Serialize)]
+struct Cadence{
+    upgrades: usize,
+    mean_hours: Option<i64>,
+    median_hours: Option<i64>,
+    longest: Option<Interval>,
+    #[serde(serialize_with = "serialize_option_dt")]
+    last_upgrade: Option<DT>,
+    hours_since_last: Option<i64>,
+    // intervals longer than the policy allows
+    over_max: usize,
+    distribution: Table<&'static str>,
+    intervals: Vec<Interval>,
+}
+
+/// When the full system upgrades ran: transactions with pacman's full upgrade line, or whose
+/// command asks for one.
+fn full_upgrades(events: &Events) -> Vec<DT>{
+    let mut upgrades: Vec<DT> = Vec::new();
+    // the current command, and whether it was counted already
+    let mut command = None;
+    for event in events{
+        match event{
+            Event::Command(dt, cmd) => {
+                let full = matches!(sync_flags(cmd), Some((_, true)));
+                if full { upgrades.push(*dt); }
+                command = Some((*dt, full));
+            },
+            Event::Marker(_, _, Marker::FullUpgrade) => {
+                if let Some((dt, false)) = command {
+                    upgrades.push(dt);
+                    command = Some((dt, true));
+                }
+            },
+            _ => { },
+        }
+    }
+    upgrades
+}
+
+/// How regularly the whole system gets upgraded: the mean and median time between full upgrades,
+/// the longest gap, the time since the last one and how the intervals are spread. Intervals of
+/// more than `max` days are counted, a policy of upgrading every two weeks is the default.
+pub fn cadence(events: Events, now: i64, max: u32, output: Output){
+    let upgrades = full_upgrades(&events);
+    let intervals = upgrades.windows(2)
+        .map(|w| Interval{ from: w[0], to: w[1], hours: hours(w[1]) - hours(w[0]) })
+        .collect::<Vec<_>>();
+    let mut sorted = intervals.iter().map(|i| i.hours).collect::<Vec<_>>();
+    sorted.sort_unstable();
+    let median_hours = match sorted.len(){
+        0 => None,
+        n if n % 2 == 1 => Some(sorted[n / 2]),
+        n => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2),
+    };
+    let mean_hours = (!sorted.is_empty())
+        .then(|| sorted.iter().sum::<i64>() / sorted.len() as i64);
+    let longest = intervals.iter().max_by_key(|i| i.hours).copied();
+    let over_max = intervals.iter().filter(|i| i.hours > max as i64 * 24).count();
+    let mut counts = [0; BUCKETS.len()];
+    for interval in &intervals{
+        let bucket = BUCKETS.iter().position(|(days, _)| interval.hours <= days * 24);
+        counts[bucket.unwrap_or(BUCKETS.len() - 1)] += 1;
+    }
+    let rows = BUCKETS.iter().zip(counts)
+        .map(|((_, label), count)| Row{
+            value: *label,
+            count,
+            percent: count as f32 / intervals.len().max(1) as f32 * 100.0,
+        })
+        .collect::<Vec<_>>();
+    let last_upgrade = upgrades.last().copied();
+    let cadence = Cadence{
+        upgrades: upgrades.len(),
+        mean_hours,
+        median_hours,
+        longest,
+        last_upgrade,
+        hours_since_last: last_upgrade.map(|last| now - hours(last)),
+        over_max,
+        distribution: Table{ total: intervals.len(), distinct: rows.len(), rows },
+        intervals,
+    };
+    if output.structured() {
+        print_structured(output, &cadence, &cadence.intervals);
+        return;
+    }
+
+    let c = colours();
+    let Cadence{ last_upgrade: Some(last_upgrade), hours_since_last: Some(hours_since_last), .. }
+        = cadence else
+    {
+        println!("No full system upgrades in the log.");
+        return;
+    };
+    let or_none = |hours: Option<i64>| hours.map_or(String::from("-"), format_hours);
+    println!("Full upgrades: {}{}{}", c.total, cadence.upgrades, c.reset);
+    println!("Mean interval: {}{}{}", c.count, or_none(cadence.mean_hours), c.reset);
+    println!("Median interval: {}{}{}", c.count, or_none(cadence.median_hours), c.reset);
+    if let Some(longest) = cadence.longest {
+        println!(
+            "Longest gap: {}{}{} from {} to {}",
+            c.count, format_hours(longest.hours), c.reset,
+            format_dt(longest.from), format_dt(longest.to)
+        );
+    }
+    let late = if hours_since_last > max as i64 * 24 { &c.remove } else { &c.count };
+    println!(
+        "Since the last upgrade: {}{}{}, {}",
+        late, format_hours(hours_since_last), c.reset, format_dt(last_upgrade)
+    );
+    println!(
+        "Gaps over {} days: {}{}{} of {}",
+        max, c.count, cadence.over_max, c.reset, cadence.distribution.total
+    );
+    if cadence.distribution.total > 0 {
+        print_bars(&cadence.distribution, "Intervals");
+    }
+}
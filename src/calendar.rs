@@ -0,0 +1,135 @@
+use std::collections::{ BTreeSet, HashMap };
+
+use serde::Serialize;
+
+use crate::{
+    Event, Events, transactions, days_from_civil,
+    colour::colours,
+    output::{ Output, Tabular, print_structured },
+    report::MONTHS,
+};
+
+type Date = (u16, u8, u8); // y, m, d
+
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+const WEEKDAYS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", "Sun"];
+
+#[derive(Serialize)]
+struct Day{
+    date: String,
+    count: usize,
+}
+
+impl Tabular for Day{
+    const HEADER: &'static [&'static str] = &["date", "count"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![self.date.clone(), self.count.to_string()]
+    }
+}
+
+/// Draws a grid per year with a column per week and a row per weekday, each day shaded by the
+/// amount of events, or of upgrade transactions with `upgrades`. Without `years` every year in the
+/// log is drawn. All years share one scale, so they can be compared.
+pub fn calendar(events: Events, years: Vec<u16>, upgrades: bool, output: Output){
+    let counts = per_date(&events, upgrades);
+    let years = if years.is_empty() {
+        counts.keys().map(|(y, _, _)| *y).collect::<BTreeSet<_>>()
+    } else {
+        years.into_iter().collect()
+    };
+    if output.structured() {
+        let mut days = counts.iter()
+            .filter(|((y, _, _), _)| years.contains(y))
+            .map(|(&(y, m, d), &count)| Day{
+                date: format!("{:0>4}-{:0>2}-{:0>2}", y, m, d),
+                count,
+            })
+            .collect::<Vec<_>>();
+        days.sort_unstable_by(|a, b| a.date.cmp(&b.date));
+        print_structured(output, &days, &days);
+        return;
+    }
+
+    let max = counts.iter()
+        .filter(|((y, _, _), _)| years.contains(y))
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+    let what = if upgrades { "upgrade transactions" } else { "events" };
+    for year in years{
+        print_year(year, &counts, max, what);
+    }
+    let c = colours();
+    print!("Less {}·{} ", c.faint, c.reset);
+    for shade in SHADES{
+        print!("{}{}{} ", c.count, shade, c.reset);
+    }
+    println!("More, {}{}{} {} on the busiest day", c.total, max, c.reset, what);
+}
+
+fn per_date(events: &Events, upgrades: bool) -> HashMap<Date, usize>{
+    let mut counts = HashMap::new();
+    if upgrades {
+        for t in transactions(events).iter().filter(|t| !t.upgrade.is_empty()){
+            let (y, m, d, _) = t.dt;
+            *counts.entry((y, m, d)).or_insert(0) += 1;
+        }
+    } else {
+        for event in events.iter().filter(|e| !matches!(e, Event::Marker(_, _, _))){
+            let (y, m, d, _) = event.dt();
+            *counts.entry((y, m, d)).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn print_year(year: u16, counts: &HashMap<Date, usize>, max: usize, what: &str){
+    let c = colours();
+    let y = year as i64;
+    let first = days_from_civil(y, 1, 1);
+    // monday is 0, 1970-01-01 was a thursday
+    let offset = (first + 3).rem_euclid(7) as usize;
+    let mut grid: Vec<Vec<Option<usize>>> = vec![vec![None; 54]; 7];
+    let mut labels = vec![' '; 54 + 3];
+    let mut total = 0;
+    for m in 1..=12u8{
+        let start = days_from_civil(y, m as i64, 1);
+        let end = if m == 12 {
+            days_from_civil(y + 1, 1, 1)
+        } else {
+            days_from_civil(y, m as i64 + 1, 1)
+        };
+        // the label goes above the first full week of the month, if it fits
+        let column = ((start - first) as usize + offset).div_ceil(7);
+        if labels[column.saturating_sub(1)..column + 3].iter().all(|ch| *ch == ' ') {
+            for (i, ch) in MONTHS[m as usize - 1].chars().enumerate(){
+                labels[column + i] = ch;
+            }
+        }
+        for day in start..end{
+            let i = (day - first) as usize + offset;
+            let count = counts.get(&(year, m, (day - start + 1) as u8)).copied().unwrap_or(0);
+            total += count;
+            grid[i % 7][i / 7] = Some(count);
+        }
+    }
+
+    println!("{}{}{}: {}{}{} {}", c.bold, year, c.reset, c.total, total, c.reset, what);
+    println!("    {}", labels.iter().collect::<String>().trim_end());
+    for (row, name) in grid.iter().zip(WEEKDAYS){
+        let mut line = format!("{:<4}", name);
+        for cell in row{
+            match cell{
+                None => line.push(' '),
+                Some(0) => line.push_str(&format!("{}·{}", c.faint, c.reset)),
+                Some(n) => {
+                    let shade = SHADES[((n * 4 - 1) / max.max(1)).min(3)];
+                    line.push_str(&format!("{}{}{}", c.count, shade, c.reset));
+                },
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+    println!();
+}
@@ -0,0 +1,71 @@
+use std::fmt::Display;
+
+use crate::{ TimeTables, colour::colours, output::{ Table, Row } };
+
+const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Like `print_table`, with a bar per row scaled so the largest count fills the terminal width.
+pub fn print_bars<T: Display>(table: &Table<T>, msg: &str){
+    let c = colours();
+    println!("{}: {}{}{}", msg, c.total, table.total, c.reset);
+    let labels = table.rows.iter().map(|r| format!("{: >2}", r.value)).collect::<Vec<_>>();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let suffix_width = table.rows.iter()
+        .map(|r| format!("{} ({:.2}%)", r.count, r.percent).len())
+        .max()
+        .unwrap_or(0);
+    let columns = term_size::dimensions().map_or(80, |(w, _)| w);
+    // a tab, the label and ": " before the bar, a space and the numbers after it
+    let width = columns.saturating_sub(8 + label_width + 2 + 1 + suffix_width).max(1);
+    let max = table.rows.iter().map(|r| r.count).max().unwrap_or(0).max(1);
+    for (label, Row{ count, percent, .. }) in labels.iter().zip(&table.rows){
+        println!(
+            "\t{}{:>label_width$}{}: {}{:<width$}{} {}{}{} ({}{:.2}%{})",
+            c.key, label, c.reset, c.count, bar(*count, max, width), c.reset,
+            c.count, count, c.reset, c.percent, percent, c.reset,
+        );
+    }
+}
+
+/// A bar of `value / max * width` characters, with eighth blocks for the remainder.
+fn bar(value: usize, max: usize, width: usize) -> String{
+    let eighths = value * width * 8 / max;
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIAL[eighths % 8]);
+    } else if eighths == 0 && value > 0 {
+        bar.push(PARTIAL[1]);
+    }
+    bar
+}
+
+/// One character per value, zero is left blank so gaps stand out.
+pub fn sparkline(values: &[usize]) -> String{
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values.iter()
+        .map(|&v| if v == 0 { ' ' } else { TICKS[((v * 8 - 1) / max).min(7)] })
+        .collect()
+}
+
+/// A sparkline per category over all `slots`, under an `axis` with a character per slot.
+pub fn print_sparklines(tables: &TimeTables, slots: &[u16], axis: &str){
+    let c = colours();
+    let categories = [
+        ("Commands", &tables.commands), ("Installs", &tables.installs),
+        ("Removes", &tables.removes), ("Upgrades", &tables.upgrades),
+        ("Downgrades", &tables.downgrades),
+    ];
+    println!("\t{:<10} {}{}{}", "", c.faint, axis, c.reset);
+    for (name, table) in categories{
+        let values = slots.iter()
+            .map(|s| table.rows.iter().find(|r| r.value == *s).map_or(0, |r| r.count))
+            .collect::<Vec<_>>();
+        println!(
+            "\t{}{:<10}{} {}{}{} {}{}{}",
+            c.key, name, c.reset, c.count, sparkline(&values), c.reset,
+            c.total, table.total, c.reset,
+        );
+    }
+    println!();
+}
@@ -0,0 +1,220 @@
+use std::{
+    env,
+    collections::HashMap,
+    io::{ self, IsTerminal },
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use simpleio::{ get_config, file_exists, read_file_into_string };
+use zen_colour::*;
+
+use crate::fail;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColourMode{
+    Auto, // only when stdout is a terminal and NO_COLOR is not set
+    Always,
+    Never,
+}
+
+/// The escape codes used by every printer, all empty when colours are off.
+/// Besides plain text attributes it holds a style per semantic role, which the theme decides.
+#[derive(Clone, Default)]
+pub struct Colours{
+    pub reset: String,
+    pub bold: String,
+    pub faint: String,
+    pub italic: String,
+    pub underlined: String,
+    pub install: String,
+    pub remove: String,
+    pub upgrade: String,
+    pub downgrade: String,
+    pub command: String,
+    pub version: String,
+    pub date: String,
+    pub count: String,
+    pub total: String,
+    pub package: String,
+    pub key: String,
+    pub percent: String,
+    pub increase: String,
+    pub decrease: String,
+    pub unchanged: String,
+}
+
+pub const THEMES: [&str; 4] = ["default", "dark", "light", "mono"];
+
+/// Role styles of a built-in theme.
+fn builtin_roles(name: &str) -> Option<[(&'static str, &'static str); 15]>{
+    let roles = match name{
+        "default" => [
+            ("install", "bold green"), ("remove", "bold red"), ("upgrade", "green"),
+            ("downgrade", "red underlined"), ("command", "magenta"),
+            ("version", "faint italic cyan"), ("date", "faint"), ("count", "red"),
+            ("total", "bold red"), ("package", "bold"), ("key", "bold green"),
+            ("percent", "yellow"), ("increase", "bold red"), ("decrease", "bold green"),
+            ("unchanged", "bold yellow"),
+        ],
+        // no faint text, it tends to disappear on dark backgrounds
+        "dark" => [
+            ("install", "bold green"), ("remove", "bold red"), ("upgrade", "green"),
+            ("downgrade", "red underlined"), ("command", "magenta"),
+            ("version", "italic cyan"), ("date", "blue"), ("count", "yellow"),
+            ("total", "bold yellow"), ("package", "bold white"), ("key", "bold cyan"),
+            ("percent", "white"), ("increase", "bold red"), ("decrease", "bold green"),
+            ("unchanged", "bold white"),
+        ],
+        // no yellow and cyan, they are hard to read on light backgrounds
+        "light" => [
+            ("install", "bold green"), ("remove", "bold red"), ("upgrade", "green"),
+            ("downgrade", "red underlined"), ("command", "magenta"),
+            ("version", "italic blue"), ("date", "faint"), ("count", "red"),
+            ("total", "bold red"), ("package", "bold black"), ("key", "bold blue"),
+            ("percent", "magenta"), ("increase", "bold red"), ("decrease", "bold green"),
+            ("unchanged", "bold black"),
+        ],
+        // only text attributes, for terminals or people without colours
+        "mono" => [
+            ("install", "bold"), ("remove", "bold crossed"), ("upgrade", ""),
+            ("downgrade", "underlined"), ("command", "italic"),
+            ("version", "faint italic"), ("date", "faint"), ("count", "bold"),
+            ("total", "bold"), ("package", "bold"), ("key", "bold"),
+            ("percent", ""), ("increase", "bold"), ("decrease", "bold"),
+            ("unchanged", "bold"),
+        ],
+        _ => return None,
+    };
+    Some(roles)
+}
+
+/// A theme file overrides roles of a base theme, for example:
+/// ```toml
+/// base = "dark"
+/// version = "italic magenta"
+/// ```
+#[derive(Deserialize)]
+struct ThemeFile{
+    base: Option<String>,
+    #[serde(flatten)]
+    roles: HashMap<String, String>,
+}
+
+impl Colours{
+    fn theme(name: &str) -> Self{
+        let mut colours = Self{
+            reset: RESET.to_string(),
+            bold: BOLD.to_string(),
+            faint: FAINT.to_string(),
+            italic: ITALIC.to_string(),
+            underlined: UNDERLINED.to_string(),
+            ..Default::default()
+        };
+        if let Some(roles) = builtin_roles(name) {
+            for (role, style) in roles{
+                colours.set_role(role, style);
+            }
+            return colours;
+        }
+        let path = PathBuf::from(name);
+        let Ok(string) = read_file_into_string(&path) else {
+            fail(format!(
+                "'{}' is not a theme ({}) or a readable theme file!", name, THEMES.join(", ")
+            ));
+        };
+        let file: ThemeFile = match toml::from_str(&string){
+            Ok(file) => file,
+            Err(e) => fail(format!("could not parse theme '{}': {}", name, e)),
+        };
+        let mut colours = Self::theme(file.base.as_deref().unwrap_or("default"));
+        for (role, style) in file.roles{
+            colours.set_role(&role, &style);
+        }
+        colours
+    }
+
+    fn set_role(&mut self, role: &str, style: &str){
+        let field = match role{
+            "install" => &mut self.install,
+            "remove" => &mut self.remove,
+            "upgrade" => &mut self.upgrade,
+            "downgrade" => &mut self.downgrade,
+            "command" => &mut self.command,
+            "version" => &mut self.version,
+            "date" => &mut self.date,
+            "count" => &mut self.count,
+            "total" => &mut self.total,
+            "package" => &mut self.package,
+            "key" => &mut self.key,
+            "percent" => &mut self.percent,
+            "increase" => &mut self.increase,
+            "decrease" => &mut self.decrease,
+            "unchanged" => &mut self.unchanged,
+            _ => fail(format!("unknown theme role '{}'!", role)),
+        };
+        *field = parse_style(style);
+    }
+}
+
+/// Turns a style like "bold italic cyan" into escape codes.
+fn parse_style(style: &str) -> String{
+    style.split_whitespace().map(|word| match word{
+        "bold" => BOLD,
+        "faint" => FAINT,
+        "italic" => ITALIC,
+        "underlined" => UNDERLINED,
+        "crossed" => CROSSED,
+        "black" => BLACK,
+        "red" => RED,
+        "green" => GREEN,
+        "yellow" => YELLOW,
+        "blue" => BLUE,
+        "magenta" => MAGENTA,
+        "cyan" => CYAN,
+        "white" => WHITE,
+        "default" => DEFAULT,
+        _ => fail(format!("unknown style '{}'!", word)),
+    }).collect()
+}
+
+/// Where a theme file is picked up when no theme is given.
+pub fn default_theme_path() -> Option<PathBuf>{
+    let mut path = get_config().ok()?;
+    path.push("paclog");
+    path.push("theme.toml");
+    Some(path)
+}
+
+static COLOURS: OnceLock<Colours> = OnceLock::new();
+
+/// Decides once whether to use colours and with which theme,
+/// must be called before anything is printed.
+pub fn init_colours(mode: ColourMode, theme: Option<&str>){
+    let on = match mode{
+        ColourMode::Always => true,
+        ColourMode::Never => false,
+        ColourMode::Auto => {
+            let no_colour = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            !no_colour && io::stdout().is_terminal()
+        },
+    };
+    let colours = if !on {
+        Colours::default()
+    } else if let Some(theme) = theme {
+        Colours::theme(theme)
+    } else {
+        match default_theme_path().filter(|p| file_exists(p)) {
+            Some(path) => Colours::theme(&path.to_string_lossy()),
+            None => Colours::theme("default"),
+        }
+    };
+    let _ = COLOURS.set(colours);
+}
+
+pub fn colours() -> &'static Colours{
+    COLOURS.get_or_init(Colours::default)
+}
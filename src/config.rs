@@ -0,0 +1,130 @@
+use std::{
+    collections::{ BTreeMap, HashMap },
+    ffi::OsString,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+use simpleio::{ get_config, file_exists, read_file_into_string };
+
+use crate::colour::ColourMode;
+
+pub const DEFAULT_LOG: &str = "/var/log/pacman.log";
+pub const DEFAULT_DATE_FORMAT: &str = "%Y/%m/%d %H:%M";
+pub const DEFAULT_CACHE: &str = "/var/cache/pacman/pkg";
+
+/// Everything that can be set in `~/.config/paclog/config.toml`.
+/// Options given on the command line always win.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config{
+    // log files to read, oldest first
+    pub logs: Vec<String>,
+    pub color: Option<ColourMode>,
+    pub theme: Option<String>,
+    pub date_format: Option<String>,
+    // packages to leave out of every report
+    pub exclude: Vec<String>,
+    // default amount of items per subcommand, by subcommand name
+    pub n: HashMap<String, usize>,
+    // package cache for rollback plans
+    pub cache_dir: Option<String>,
+    pub alias: BTreeMap<String, Alias>,
+}
+
+/// An alias is either a single string, split on whitespace, or a list of arguments.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Alias{
+    Line(String),
+    Args(Vec<String>),
+}
+
+impl Alias{
+    fn args(&self) -> Vec<String>{
+        match self{
+            Alias::Line(line) => line.split_whitespace().map(str::to_string).collect(),
+            Alias::Args(args) => args.clone(),
+        }
+    }
+}
+
+pub fn config_path() -> Option<PathBuf>{
+    let mut path = get_config().ok()?;
+    path.push("paclog");
+    path.push("config.toml");
+    Some(path)
+}
+
+impl Config{
+    /// Reads the config file, a missing file is the same as an empty one.
+    pub fn load() -> Self{
+        let Some(path) = config_path().filter(|p| file_exists(p)) else {
+            return Self::default();
+        };
+        let string = match read_file_into_string(&path){
+            Ok(string) => string,
+            Err(e) => panic!("Error: could not read '{}': {}", path.display(), e),
+        };
+        match toml::from_str(&string){
+            Ok(config) => config,
+            Err(e) => panic!("Error: could not parse '{}': {}", path.display(), e),
+        }
+    }
+
+    /// Replaces the first subcommand-like argument with its alias, if it is one.
+    /// Arguments before and after it stay, so aliases can be combined with extra flags.
+    /// `with_value` are the options before the subcommand that take a value.
+    pub fn expand_alias(
+        &self, args: Vec<OsString>, with_value: &[&str], subcommands: &[&str]
+    ) -> Vec<OsString>{
+        let mut skip_value = false;
+        let mut position = None;
+        for (i, arg) in args.iter().enumerate().skip(1){
+            if skip_value {
+                skip_value = false;
+                continue;
+            }
+            let Some(arg) = arg.to_str() else { break; };
+            if with_value.contains(&arg) {
+                skip_value = true;
+            } else if !arg.starts_with('-') {
+                position = Some(i);
+                break;
+            } else if arg == "--" {
+                break;
+            }
+        }
+        let Some(i) = position else { return args; };
+        let name = args[i].to_str().unwrap_or("");
+        // built-in subcommands can't be shadowed
+        if subcommands.contains(&name) { return args; }
+        let Some(alias) = self.alias.get(name) else { return args; };
+        let mut expanded = args[..i].to_vec();
+        expanded.extend(alias.args().into_iter().map(OsString::from));
+        expanded.extend(args[i + 1..].iter().cloned());
+        expanded
+    }
+
+    /// A help section listing the aliases, if there are any.
+    pub fn alias_help(&self) -> Option<String>{
+        if self.alias.is_empty() { return None; }
+        let width = self.alias.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+        let mut help = String::from("Aliases:");
+        for (name, alias) in &self.alias{
+            help.push_str(&format!("\n  {:<width$}  {}", name, alias.args().join(" ")));
+        }
+        Some(help)
+    }
+}
+
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+
+pub fn init_date_format(format: Option<String>){
+    let _ = DATE_FORMAT.set(format.unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string()));
+}
+
+pub fn date_format() -> &'static str{
+    DATE_FORMAT.get().map_or(DEFAULT_DATE_FORMAT, |f| f)
+}
@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+
+use crate::{
+    DT, Events, replay, format_dt,
+    colour::colours,
+    output::{ Output, Tabular, print_structured },
+    state::events_before,
+    version::vercmp,
+};
+
+#[derive(Serialize)]
+struct Change<'a>{
+    package: &'a str,
+    change: &'static str,
+    old_version: Option<&'a str>,
+    new_version: Option<&'a str>,
+    // amount of package events in between, more than one when it changed several times
+    events: usize,
+}
+
+impl Tabular for Change<'_>{
+    const HEADER: &'static [&'static str] =
+        &["package", "change", "old_version", "new_version", "events"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            self.change.to_string(),
+            self.old_version.unwrap_or("").to_string(),
+            self.new_version.unwrap_or("").to_string(),
+            self.events.to_string(),
+        ]
+    }
+}
+
+/// What changed in the installed packages between two points in time, from the versions at each
+/// end. A package that changed several times in between is still one line.
+pub fn diff(events: Events, from: DT, to: Option<DT>, output: Output){
+    let before = events_before(&events, from);
+    let after = match to{
+        Some(to) => events_before(&events, to),
+        None => &events,
+    };
+    let (old, new) = (replay(before), replay(after));
+    let between = &after[before.len().min(after.len())..];
+    let count = |package: &str| between.iter().filter(|e| e.package() == Some(package)).count();
+
+    let mut changes = Vec::new();
+    for (package, version) in &new{
+        let (change, old_version) = match old.get(package){
+            None => ("added", None),
+            Some(old) => match vercmp(old, version){
+                Ordering::Less => ("upgraded", Some(*old)),
+                Ordering::Greater => ("downgraded", Some(*old)),
+                Ordering::Equal => continue,
+            },
+        };
+        changes.push(Change{
+            package, change, old_version, new_version: Some(version), events: count(package),
+        });
+    }
+    for (package, version) in old.iter().filter(|(p, _)| !new.contains_key(*p)){
+        changes.push(Change{
+            package, change: "removed", old_version: Some(version), new_version: None,
+            events: count(package),
+        });
+    }
+    let order = ["added", "removed", "upgraded", "downgraded"];
+    changes.sort_by_key(|c| (order.iter().position(|o| *o == c.change), c.package));
+    if output.structured() {
+        print_structured(output, &changes, &changes);
+        return;
+    }
+
+    let c = colours();
+    match to{
+        Some(to) => println!("From {} to {}", format_dt(from), format_dt(to)),
+        None => println!("From {} to now", format_dt(from)),
+    }
+    for (kind, heading, style) in [
+        ("added", "Added", &c.install), ("removed", "Removed", &c.remove),
+        ("upgraded", "Upgraded", &c.upgrade), ("downgraded", "Downgraded", &c.downgrade),
+    ]{
+        let group = changes.iter().filter(|c| c.change == kind).collect::<Vec<_>>();
+        if group.is_empty() { continue; }
+        println!("{}{}{}: {}{}{}", style, heading, c.reset, c.total, group.len(), c.reset);
+        for change in group{
+            let versions = match (change.old_version, change.new_version){
+                (Some(old), Some(new)) => format!("{} -> {}", old, new),
+                (old, new) => old.or(new).unwrap_or("").to_string(),
+            };
+            let times = if change.events > 1 {
+                format!(" {}({} changes){}", c.faint, change.events, c.reset)
+            } else {
+                String::new()
+            };
+            println!(
+                "\t{}{}{} {}{}{}{}",
+                c.package, change.package, c.reset, c.version, versions, c.reset, times
+            );
+        }
+    }
+    if changes.is_empty() {
+        println!("No changes.");
+    }
+}
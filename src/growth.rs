@@ -0,0 +1,199 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Events, Transaction, transactions, hours, format_dt, strftime,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+    report::escape_html,
+};
+
+const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The amount of installed packages after a transaction that changed it.
+#[derive(Serialize)]
+struct Point<'a>{
+    #[serde(rename = "date", serialize_with = "serialize_dt")]
+    dt: DT,
+    packages: i32,
+    change: i32,
+    command: &'a str,
+    #[serde(skip)]
+    hour: i64, // hours since the epoch, for the x axis
+}
+
+impl Tabular for Point<'_>{
+    const HEADER: &'static [&'static str] = &["date", "packages", "change", "command"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            iso_dt(self.dt),
+            self.packages.to_string(),
+            self.change.to_string(),
+            self.command.to_string(),
+        ]
+    }
+}
+
+/// Plots the amount of installed packages over time, in the terminal or as an SVG image.
+/// The `jumps` biggest changes are numbered on the chart and listed below it.
+pub fn growth(events: Events, jumps: usize, svg: bool, output: Output){
+    let history = transactions(&events);
+    let points = points(&history);
+    if output.structured() {
+        print_structured(output, &points, &points);
+        return;
+    }
+    if points.is_empty() {
+        println!("No transactions changed the amount of packages.");
+        return;
+    }
+    let mut biggest = (0..points.len()).collect::<Vec<_>>();
+    biggest.sort_by_key(|i| -points[*i].change.abs());
+    biggest.truncate(jumps);
+    biggest.sort_unstable();
+    if svg {
+        print!("{}", svg_chart(&points, &biggest));
+    } else {
+        terminal_chart(&points, &biggest);
+    }
+}
+
+fn points(history: &[Transaction]) -> Vec<Point<'_>>{
+    history.iter()
+        .filter(|t| t.install.len() != t.remove.len())
+        .map(|t| Point{
+            dt: t.dt,
+            packages: t.packages,
+            change: t.install.len() as i32 - t.remove.len() as i32,
+            command: &t.command,
+            hour: hours(t.dt),
+        })
+        .collect()
+}
+
+/// Lowest and highest amount of packages, never the same so they can be divided by.
+fn range(points: &[Point]) -> (i32, i32){
+    let lo = points.iter().map(|p| p.packages).min().unwrap_or(0);
+    let hi = points.iter().map(|p| p.packages).max().unwrap_or(0);
+    (lo, hi.max(lo + 1))
+}
+
+fn terminal_chart(points: &[Point], biggest: &[usize]){
+    const HEIGHT: usize = 12;
+    const AXIS: usize = 7;
+    let c = colours();
+    let columns = term_size::dimensions().map_or(80, |(w, _)| w);
+    let width = columns.saturating_sub(AXIS + 1).max(10);
+    let (lo, hi) = range(points);
+    let (t0, t1) = (points[0].hour, points[points.len() - 1].hour.max(points[0].hour + 1));
+    let column = |hour: i64| ((hour - t0) * (width as i64 - 1) / (t1 - t0)) as usize;
+
+    // the amount of packages at the start of each column, in eighths of a row
+    let mut levels = Vec::with_capacity(width);
+    let mut i = 0;
+    for col in 0..width{
+        while i + 1 < points.len() && column(points[i + 1].hour) <= col{
+            i += 1;
+        }
+        let v = (points[i].packages - lo) as usize;
+        levels.push(1 + v * (HEIGHT * 8 - 1) / (hi - lo) as usize);
+    }
+    for row in (0..HEIGHT).rev(){
+        let label = match row{
+            r if r == HEIGHT - 1 => hi.to_string(),
+            0 => lo.to_string(),
+            _ => String::new(),
+        };
+        let mut line = format!("{:>w$} │{}", label, c.count, w = AXIS - 2);
+        for level in &levels{
+            let base = row * 8;
+            line.push(if *level >= base + 8 {
+                '█'
+            } else if *level > base {
+                TICKS[level - base - 1]
+            } else {
+                ' '
+            });
+        }
+        println!("{}{}", line.trim_end(), c.reset);
+    }
+    println!("{:>w$}└{}", "", "─".repeat(width), w = AXIS - 1);
+
+    let mut markers = vec![' '; width];
+    for (n, i) in biggest.iter().enumerate(){
+        if let Some(marker) = char::from_digit(n as u32 + 1, 36) {
+            markers[column(points[*i].hour)] = marker;
+        }
+    }
+    let markers = markers.into_iter().collect::<String>();
+    println!("{:>w$}{}{}{}", "", c.key, markers.trim_end(), c.reset, w = AXIS);
+    let first = strftime(points[0].dt, "%Y/%m/%d");
+    let last = strftime(points[points.len() - 1].dt, "%Y/%m/%d");
+    println!("{:>w$}{}{:>r$}", "", first, last, w = AXIS, r = width.saturating_sub(first.len()));
+    println!();
+
+    for (n, i) in biggest.iter().enumerate(){
+        let Point{ dt, packages, change, command, .. } = &points[*i];
+        let colour = if *change > 0 { &c.increase } else { &c.decrease };
+        println!(
+            "{}{}{} {} {}{:+}{} -> {} {}{}{}",
+            c.key, char::from_digit(n as u32 + 1, 36).unwrap_or('?'), c.reset,
+            format_dt(*dt), colour, change, c.reset, packages, c.command, command, c.reset,
+        );
+    }
+}
+
+fn svg_chart(points: &[Point], biggest: &[usize]) -> String{
+    const WIDTH: i64 = 800;
+    const HEIGHT: i64 = 300;
+    const LEFT: i64 = 50;
+    const RIGHT: i64 = 20;
+    const TOP: i64 = 20;
+    const BOTTOM: i64 = 30;
+    let (lo, hi) = range(points);
+    let (t0, t1) = (points[0].hour, points[points.len() - 1].hour.max(points[0].hour + 1));
+    let x = |hour: i64| LEFT + (hour - t0) * (WIDTH - LEFT - RIGHT) / (t1 - t0);
+    let y = |v: i32| TOP + (hi - v) as i64 * (HEIGHT - TOP - BOTTOM) / (hi - lo) as i64;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+        font-family=\"sans-serif\" font-size=\"12\">\n\
+        <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        WIDTH, HEIGHT
+    );
+    // axes with the lowest and highest amount and the first and last date
+    svg.push_str(&format!(
+        "<path d=\"M {l} {t} V {b} H {r}\" stroke=\"#888\" fill=\"none\"/>\n\
+        <text x=\"{lx}\" y=\"{hy}\" text-anchor=\"end\">{hi}</text>\n\
+        <text x=\"{lx}\" y=\"{ly}\" text-anchor=\"end\">{lo}</text>\n\
+        <text x=\"{l}\" y=\"{dy}\">{first}</text>\n\
+        <text x=\"{r}\" y=\"{dy}\" text-anchor=\"end\">{last}</text>\n",
+        l = LEFT, t = TOP, b = HEIGHT - BOTTOM, r = WIDTH - RIGHT,
+        lx = LEFT - 6, hy = y(hi) + 4, ly = y(lo) + 4, hi = hi, lo = lo,
+        dy = HEIGHT - BOTTOM + 18,
+        first = strftime(points[0].dt, "%Y-%m-%d"),
+        last = strftime(points[points.len() - 1].dt, "%Y-%m-%d"),
+    ));
+    // a step line, the amount stays the same until the next transaction
+    let mut path = format!("M {} {}", x(points[0].hour), y(points[0].packages));
+    for p in &points[1..]{
+        path.push_str(&format!(" H {} V {}", x(p.hour), y(p.packages)));
+    }
+    path.push_str(&format!(" H {}", WIDTH - RIGHT));
+    svg.push_str(&format!(
+        "<path d=\"{}\" stroke=\"#4878a8\" stroke-width=\"2\" fill=\"none\"/>\n", path
+    ));
+    for (n, i) in biggest.iter().enumerate(){
+        let p = &points[*i];
+        let colour = if p.change > 0 { "#c03030" } else { "#30a030" };
+        svg.push_str(&format!(
+            "<g><title>{} {:+} -> {}: {}</title>\
+            <circle cx=\"{x}\" cy=\"{y}\" r=\"4\" fill=\"{}\"/>\
+            <text x=\"{x}\" y=\"{ty}\" text-anchor=\"middle\">{}</text></g>\n",
+            strftime(p.dt, "%Y-%m-%d %H:00"), p.change, p.packages, escape_html(p.command),
+            colour, n + 1, x = x(p.hour), y = y(p.packages), ty = y(p.packages) - 8,
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
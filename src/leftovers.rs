@@ -0,0 +1,92 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Events, transactions, replay, format_dt,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+};
+
+/// A named package that was removed, with the dependencies it brought that are still installed.
+#[derive(Serialize)]
+struct Leftover{
+    package: String,
+    #[serde(serialize_with = "serialize_dt")]
+    installed: DT,
+    install_command: String,
+    #[serde(serialize_with = "serialize_dt")]
+    removed: DT,
+    remove_command: String,
+    leftovers: Vec<String>,
+}
+
+impl Tabular for Leftover{
+    const HEADER: &'static [&'static str] = &[
+        "package", "installed", "install_command", "removed", "remove_command", "leftovers",
+    ];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.clone(),
+            iso_dt(self.installed),
+            self.install_command.clone(),
+            iso_dt(self.removed),
+            self.remove_command.clone(),
+            self.leftovers.join(" "),
+        ]
+    }
+}
+
+/// For every transaction that installed named packages, checks whether those were removed later
+/// while packages that came along in the same transaction are still installed.
+/// Those may still be needed by something else, but often they are forgotten dependencies.
+pub fn leftovers(events: Events, output: Output){
+    let history = transactions(&events);
+    let installed = replay(&events);
+    let mut found = Vec::new();
+    for (i, t) in history.iter().enumerate(){
+        let dependencies = t.install.iter()
+            .filter(|p| t.unnamed.contains(p))
+            .collect::<Vec<_>>();
+        if dependencies.is_empty() { continue; }
+        for package in t.install.iter().filter(|p| t.named.contains(p)){
+            if installed.contains_key(package.as_str()) { continue; }
+            let Some(removal) = history[i + 1..].iter().find(|r| r.remove.contains(package)) else {
+                continue;
+            };
+            let leftovers = dependencies.iter()
+                .filter(|d| installed.contains_key(d.as_str()))
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>();
+            if leftovers.is_empty() { continue; }
+            found.push(Leftover{
+                package: package.clone(),
+                installed: t.dt,
+                install_command: t.command.clone(),
+                removed: removal.dt,
+                remove_command: removal.command.clone(),
+                leftovers,
+            });
+        }
+    }
+    if output.structured() {
+        print_structured(output, &found, &found);
+        return;
+    }
+
+    let c = colours();
+    for l in &found{
+        println!(
+            "{}{}{} {}installed{} {} with: {}{}{}",
+            c.package, l.package, c.reset, c.install, c.reset,
+            format_dt(l.installed), c.command, l.install_command, c.reset
+        );
+        println!(
+            "\t{}removed{} {} with: {}{}{}",
+            c.remove, c.reset, format_dt(l.removed), c.command, l.remove_command, c.reset
+        );
+        println!("\tleft behind: {}{}{}", c.bold, l.leftovers.join(", "), c.reset);
+    }
+    if found.is_empty() {
+        println!("No dependencies were left behind.");
+    }
+}
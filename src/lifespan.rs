@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, hours, format_dt, format_hours,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LifespanSort{
+    Longest, // most time installed
+    Shortest, // least time installed
+    Cycles, // most times installed and removed again
+}
+
+/// A stretch of time a package was installed, with the commands that started and ended it.
+pub struct Stretch<'a>{
+    pub installed: DT,
+    pub install_command: Option<&'a str>,
+    // none while still installed
+    pub removed: Option<(DT, Option<&'a str>)>,
+}
+
+/// Every stretch of every package that was ever installed, by package.
+pub fn stretches(events: &Events) -> BTreeMap<&str, Vec<Stretch<'_>>>{
+    let mut map: BTreeMap<&str, Vec<Stretch>> = BTreeMap::new();
+    let mut last_command = None;
+    for event in events{
+        match event{
+            Event::Command(_, command) => last_command = Some(command.as_str()),
+            Event::Installed(dt, package, _) => map.entry(package).or_default().push(Stretch{
+                installed: *dt,
+                install_command: last_command,
+                removed: None,
+            }),
+            Event::Removed(dt, package, _) => {
+                let stretch = map.get_mut(package.as_str()).and_then(|s| s.last_mut());
+                if let Some(stretch) = stretch.filter(|s| s.removed.is_none()) {
+                    stretch.removed = Some((*dt, last_command));
+                }
+            },
+            _ => { },
+        }
+    }
+    map
+}
+
+#[derive(Serialize)]
+struct Lifespan<'a>{
+    package: &'a str,
+    installed_hours: i64,
+    installs: usize,
+    cycles: usize,
+    // none when not installed
+    current_hours: Option<i64>,
+    #[serde(serialize_with = "serialize_dt")]
+    first_seen: DT,
+    #[serde(serialize_with = "serialize_dt")]
+    last_seen: DT,
+}
+
+impl Tabular for Lifespan<'_>{
+    const HEADER: &'static [&'static str] = &[
+        "package", "installed_hours", "installs", "cycles", "current_hours",
+        "first_seen", "last_seen",
+    ];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            self.installed_hours.to_string(),
+            self.installs.to_string(),
+            self.cycles.to_string(),
+            self.current_hours.map_or(String::new(), |h| h.to_string()),
+            iso_dt(self.first_seen),
+            iso_dt(self.last_seen),
+        ]
+    }
+}
+
+/// Per package how long it was installed in total, how often it was installed and removed again,
+/// how long it has been installed now and when it was first and last seen in the log.
+pub fn lifespan(events: Events, now: i64, sort: LifespanSort, n: usize, all: bool, output: Output){
+    let mut seen: BTreeMap<&str, (DT, DT)> = BTreeMap::new();
+    for event in &events{
+        let Some(package) = event.package() else { continue; };
+        seen.entry(package).or_insert((event.dt(), event.dt())).1 = event.dt();
+    }
+    let mut lifespans = stretches(&events).into_iter().map(|(package, stretches)| {
+        let end = |s: &Stretch| s.removed.map_or(now, |(dt, _)| hours(dt));
+        let current = stretches.last()
+            .filter(|s| s.removed.is_none())
+            .map(|s| now - hours(s.installed));
+        let (first_seen, last_seen) = seen[package];
+        Lifespan{
+            package,
+            installed_hours: stretches.iter().map(|s| end(s) - hours(s.installed)).sum(),
+            installs: stretches.len(),
+            cycles: stretches.iter().filter(|s| s.removed.is_some()).count(),
+            current_hours: current,
+            first_seen,
+            last_seen,
+        }
+    }).collect::<Vec<_>>();
+    match sort{
+        LifespanSort::Longest => lifespans.sort_by_key(|l| -l.installed_hours),
+        LifespanSort::Shortest => lifespans.sort_by_key(|l| l.installed_hours),
+        LifespanSort::Cycles => lifespans.sort_by_key(|l| (-(l.cycles as i64), -l.installed_hours)),
+    }
+    if !all {
+        lifespans.truncate(n);
+    }
+    if output.structured() {
+        print_structured(output, &lifespans, &lifespans);
+        return;
+    }
+
+    let c = colours();
+    let width = lifespans.iter().map(|l| l.package.chars().count()).max().unwrap_or(0).max(7);
+    println!(
+        "{}{:<width$}  {:>9}  {:>6}  {:>9}  first and last seen{}",
+        c.bold, "package", "installed", "cycles", "current", c.reset
+    );
+    for l in &lifespans{
+        let current = l.current_hours.map_or("-".to_string(), format_hours);
+        println!(
+            "{}{:<width$}{}  {}{:>9}{}  {}{:>6}{}  {}{:>9}{}  {} - {}",
+            c.package, l.package, c.reset,
+            c.count, format_hours(l.installed_hours), c.reset,
+            c.count, l.cycles, c.reset,
+            c.install, current, c.reset,
+            format_dt(l.first_seen), format_dt(l.last_seen),
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct Dropped<'a>{
+    package: &'a str,
+    #[serde(serialize_with = "serialize_dt")]
+    installed: DT,
+    #[serde(serialize_with = "serialize_dt")]
+    removed: DT,
+    kept_hours: i64,
+    install_command: Option<&'a str>,
+    remove_command: Option<&'a str>,
+}
+
+impl Tabular for Dropped<'_>{
+    const HEADER: &'static [&'static str] = &[
+        "package", "installed", "removed", "kept_hours", "install_command", "remove_command",
+    ];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            iso_dt(self.installed),
+            iso_dt(self.removed),
+            self.kept_hours.to_string(),
+            self.install_command.unwrap_or("").to_string(),
+            self.remove_command.unwrap_or("").to_string(),
+        ]
+    }
+}
+
+/// Packages that were installed and removed again within `days`, tried and dropped.
+/// Only packages named in the install command count, unless `deps` is set, as dependencies
+/// come and go with the packages that need them.
+pub fn dropped(events: Events, days: u32, deps: bool, output: Output){
+    let named = |package: &str, command: Option<&str>| {
+        command.is_some_and(|c| c.split(' ').any(|word| word == package))
+    };
+    let mut dropped = Vec::new();
+    for (package, stretches) in stretches(&events){
+        for s in stretches{
+            let Some((removed, remove_command)) = s.removed else { continue; };
+            let kept_hours = hours(removed) - hours(s.installed);
+            if kept_hours > days as i64 * 24 { continue; }
+            if !deps && !named(package, s.install_command) { continue; }
+            dropped.push(Dropped{
+                package,
+                installed: s.installed,
+                removed,
+                kept_hours,
+                install_command: s.install_command,
+                remove_command,
+            });
+        }
+    }
+    dropped.sort_by_key(|d| d.installed);
+    if output.structured() {
+        print_structured(output, &dropped, &dropped);
+        return;
+    }
+
+    let c = colours();
+    for d in &dropped{
+        println!(
+            "{}{}{} kept for {}{}{}",
+            c.package, d.package, c.reset, c.count, format_hours(d.kept_hours), c.reset
+        );
+        println!(
+            "\t{} {}installed{} with: {}{}{}",
+            format_dt(d.installed), c.install, c.reset,
+            c.command, d.install_command.unwrap_or("?"), c.reset
+        );
+        println!(
+            "\t{} {}removed{} with: {}{}{}",
+            format_dt(d.removed), c.remove, c.reset,
+            c.command, d.remove_command.unwrap_or("?"), c.reset
+        );
+    }
+    if dropped.is_empty() {
+        println!("No packages were removed within {} days of installing them.", days);
+    }
+}
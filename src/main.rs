@@ -1,32 +1,108 @@
 use std::{
-    collections::{ HashMap, HashSet },
+    env,
+    collections::{ BTreeMap, HashMap, HashSet },
     num::ParseIntError,
     hash::Hash,
     fmt::{ self, Display, Write },
+    path::PathBuf,
 };
 
 use clap::{
     Parser,
     Subcommand,
+    CommandFactory,
+    FromArgMatches,
+    parser::ValueSource,
+    error::ErrorKind,
 };
+use serde::Serialize;
 
-use zen_colour::*;
 use vec_string::*;
 use simpleio::read_lines;
 
+mod output;
+mod colour;
+mod config;
+mod report;
+mod metrics;
+mod chart;
+mod calendar;
+mod growth;
+mod tui;
+mod pager;
+mod lifespan;
+mod leftovers;
+mod why;
+mod state;
+mod version;
+mod diff;
+mod rollback;
+mod bisect;
+mod warnings;
+mod regressions;
+mod stale;
+mod cadence;
+
+use output::*;
+use colour::*;
+use config::*;
+use report::*;
+use metrics::*;
+use chart::*;
+use calendar::*;
+use growth::*;
+use tui::*;
+use pager::*;
+use lifespan::*;
+use leftovers::*;
+use why::*;
+use state::*;
+use diff::*;
+use rollback::*;
+use bisect::*;
+use warnings::*;
+use regressions::*;
+use stale::*;
+use cadence::*;
+
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, args_override_self = true)]
 struct Args{
     #[clap(subcommand)]
     command: Commands,
-    #[clap(short = 'l', default_value = "/var/log/pacman.log", help = "Path to logfile.")]
-    path: String,
+    #[clap(
+        short = 'l',
+        help = "Path to logfile, can be given more than once. [default: /var/log/pacman.log]"
+    )]
+    path: Vec<String>,
+    #[clap(
+        long, global = true, value_enum, default_value_t = Output::Text, help = "Output format."
+    )]
+    output: Output,
+    #[clap(
+        long = "color", global = true, value_enum,
+        help = "When to use colours. [default: auto]"
+    )]
+    colour: Option<ColourMode>,
+    #[clap(
+        long, global = true,
+        help = "Colour theme: default, dark, light, mono or a path to a theme file."
+    )]
+    theme: Option<String>,
+    #[clap(
+        long, global = true,
+        help = "Don't show long output in $PAGER. [default pager: less -R]"
+    )]
+    no_pager: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands{
     #[clap(short_flag = 's', about = "Print some statistics.")]
-    Summary,
+    Summary{
+        #[clap(short = 'g', long, help = "Draw bar charts instead of lists.")]
+        chart: bool,
+    },
     #[allow(clippy::enum_variant_names)]
     #[clap(short_flag = 'c', about = "List most run commands.")]
     Commands{
@@ -68,6 +144,11 @@ enum Commands{
         package: String,
         #[clap(long, help = "Show command used to do upgrades.")]
         upgrade_command: bool,
+        #[clap(
+            long, value_parser = parse_template,
+            help = "Print every event with a template, like '{date:%Y-%m-%d} {kind} {old}->{new}'."
+        )]
+        format: Option<Template>,
     },
     #[clap(short_flag = 'H', about = "List pacman history.")]
     History{
@@ -79,6 +160,11 @@ enum Commands{
         no_upgrades: bool,
         #[clap(short = 'c', help = "Focus on package count.")]
         count: bool,
+        #[clap(
+            long, value_parser = parse_template,
+            help = "Print every package event with a template, implies -f."
+        )]
+        format: Option<Template>,
     },
     #[clap(
         short_flag = 'I',
@@ -100,55 +186,316 @@ enum Commands{
         day: bool,
         #[clap(short = 'H', help = "Print stats per hour.")]
         hour: bool,
+        #[clap(short = 'g', long, help = "Draw bar charts, and sparklines per month and hour.")]
+        chart: bool,
+    },
+    #[clap(about = "Write a markdown or html report with statistics, charts and recent history.")]
+    Report{
+        #[clap(long, value_enum, default_value_t = ReportFormat::Md, help = "Report format.")]
+        format: ReportFormat,
+        #[clap(short, default_value_t = 10, help = "Amount of items in top lists.")]
+        n: usize,
+        #[clap(long, default_value_t = 20, help = "Amount of recent transactions to show.")]
+        recent: usize,
+    },
+    #[clap(about = "List how long packages were installed and how often they came and went.")]
+    Lifespan{
+        #[clap(
+            long, value_enum, default_value_t = LifespanSort::Longest, help = "What to sort by."
+        )]
+        sort: LifespanSort,
+        #[clap(short, default_value_t = 16, help = "Amount of packages to show.")]
+        n: usize,
+        #[clap(short, default_value_t = false, help = "Show all.")]
+        a: bool,
+    },
+    #[clap(about = "List packages that were removed again shortly after installing them.")]
+    Dropped{
+        #[clap(
+            short = 'w', long, default_value_t = 7,
+            help = "Most days between installing and removing."
+        )]
+        within: u32,
+        #[clap(long, help = "Include dependencies, not only packages named in the command.")]
+        deps: bool,
     },
+    #[clap(about = "List dependencies that stayed after the package that brought them was removed.")]
+    Leftovers,
+    #[clap(about = "Tell which command brought a package in, and whether it was asked for.")]
+    Why{
+        package: String,
+    },
+    #[clap(about = "List the installed packages with their versions at a point in time.")]
+    State{
+        #[clap(
+            long, value_parser = parse_date,
+            help = "Date like 2024-03-01 or 2024-03-01T14, the state at its start. [default: now]"
+        )]
+        at: Option<DT>,
+    },
+    #[clap(about = "List what changed in the installed packages between two dates.")]
+    Diff{
+        #[clap(value_parser = parse_date, help = "Date like 2024-03-01, from its start.")]
+        from: DT,
+        #[clap(
+            value_parser = parse_date, help = "Date like 2024-03-08, to its start. [default: now]"
+        )]
+        to: Option<DT>,
+    },
+    #[clap(about = "Print how to go back to the package versions of a date, using the cache.")]
+    Rollback{
+        #[clap(
+            value_parser = parse_date, required_unless_present = "undo",
+            help = "Date like 2024-03-01T14, go back to its start."
+        )]
+        at: Option<DT>,
+        #[clap(
+            long, conflicts_with = "at", value_parser = clap::value_parser!(u64).range(1..),
+            help = "Go back before the last N transactions."
+        )]
+        undo: Option<u64>,
+        #[clap(long, help = "Package cache to look in. [default: /var/cache/pacman/pkg]")]
+        cache: Option<String>,
+    },
+    #[clap(about = "Find the upgrade that broke something by halving, like git bisect.")]
+    Bisect{
+        #[clap(subcommand)]
+        step: BisectStep,
+        #[clap(
+            long, global = true,
+            help = "Package cache to look in. [default: /var/cache/pacman/pkg]"
+        )]
+        cache: Option<String>,
+    },
+    #[clap(about = "Warn about partial upgrades: syncs without a full upgrade and what followed.")]
+    Warnings{
+        #[clap(
+            short = 'g', long, default_value_t = 1,
+            help = "Days between a sync and the next full upgrade that count as late."
+        )]
+        gap: u32,
+    },
+    #[clap(about = "Tell for every downgrade which upgrade it reverted and what came after.")]
+    Regressions,
+    #[clap(about = "List installed packages that were not upgraded for a long time.")]
+    Stale{
+        #[clap(
+            short = 'd', long, default_value_t = 180,
+            help = "Least days since the package was installed or upgraded."
+        )]
+        days: u32,
+    },
+    #[clap(about = "Tell how regularly full system upgrades ran, and the longest gaps.")]
+    Cadence{
+        #[clap(
+            short = 'm', long, default_value_t = 14,
+            help = "Most days there should be between full upgrades."
+        )]
+        max: u32,
+    },
+    #[clap(about = "Draw a calendar with the amount of activity per day.")]
+    Calendar{
+        #[clap(
+            short = 'y',
+            help = "Year to draw, can be given more than once. [default: every year in the log]"
+        )]
+        year: Vec<u16>,
+        #[clap(short = 'u', help = "Count upgrade transactions instead of events.")]
+        upgrades: bool,
+    },
+    #[clap(about = "Plot the amount of installed packages over time.")]
+    Growth{
+        #[clap(short = 'j', default_value_t = 3, help = "Amount of biggest changes to point out.")]
+        jumps: usize,
+        #[clap(long, help = "Print a standalone SVG image instead.")]
+        svg: bool,
+    },
+    #[clap(about = "Browse and search transactions interactively.")]
+    Tui,
+    #[clap(about = "Print metrics in Prometheus format, for node_exporter's textfile collector.")]
+    Metrics{
+        #[clap(short = 'o', help = "Write to this file instead of stdout, replacing it atomically.")]
+        file: Option<PathBuf>,
+    },
+}
+
+impl Commands{
+    /// The amount of items to show, for subcommands that have one.
+    fn n_mut(&mut self) -> Option<&mut usize>{
+        match self{
+            Commands::Commands{ n, .. } |
+            Commands::Installs{ n, .. } |
+            Commands::Removes{ n, .. } |
+            Commands::Upgrades{ n, .. } |
+            Commands::Downgrades{ n, .. } |
+            Commands::History{ n, .. } |
+            Commands::Report{ n, .. } |
+            Commands::Lifespan{ n, .. } => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Subcommands with a format of their own, `--output` does not apply to them.
+    fn own_format(&self) -> bool{
+        matches!(self, Commands::Report{ .. } | Commands::Metrics{ .. } | Commands::Tui)
+    }
 }
 
 fn main() {
-    let args = Args::parse();
-    let lines = read_lines(&args.path);
-    if lines.is_empty() {
-        panic!("Error: could not read '{}'!", args.path);
+    let config = Config::load();
+    let mut command = Args::command();
+    let subcommands = command.get_subcommands()
+        .map(|s| s.get_name().to_string())
+        .chain(["help".to_string()])
+        .collect::<Vec<_>>();
+    let subcommands = subcommands.iter().map(String::as_str).collect::<Vec<_>>();
+    let argv = config.expand_alias(
+        env::args_os().collect(), &["-l", "--output", "--color", "--theme"], &subcommands
+    );
+    if let Some(help) = config.alias_help() {
+        command = command.after_help(help);
+    }
+    let matches = command.get_matches_from(&argv);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.output != Output::Text && args.command.own_format() {
+        let name = matches.subcommand_name().unwrap_or_default();
+        let message = format!("'{}' has its own format, --output does not apply", name);
+        Args::command().error(ErrorKind::ArgumentConflict, message).exit();
+    }
+    if let (Some((name, sub)), Some(n)) = (matches.subcommand(), args.command.n_mut()) {
+        if sub.value_source("n") == Some(ValueSource::DefaultValue) {
+            if let Some(default) = config.n.get(name) {
+                *n = *default;
+            }
+        }
+    }
+
+    init_colours(
+        paged_colour().or(args.colour).or(config.color).unwrap_or(ColourMode::Auto),
+        args.theme.as_deref().or(config.theme.as_deref()),
+    );
+    init_date_format(config.date_format.clone());
+    if !args.no_pager && !matches!(args.command, Commands::Tui) && wants_pager() {
+        run_paged(&argv);
+    }
+
+    let paths = if !args.path.is_empty() {
+        args.path.clone()
+    } else if !config.logs.is_empty() {
+        config.logs.clone()
+    } else {
+        vec![DEFAULT_LOG.to_string()]
     };
-    let parsed = parse(lines);
+    let mut lines = Vec::new();
+    for path in &paths{
+        let file_lines = read_lines(path);
+        if file_lines.is_empty() {
+            panic!("Error: could not read '{}'!", path);
+        };
+        lines.extend(file_lines);
+    }
+    let (mut parsed, offset) = parse(lines);
+    let now = hours_now(offset);
+    parsed.retain(|event| event.package().is_none_or(|p| !config.exclude.iter().any(|e| e == p)));
+    let output = args.output;
 
     match args.command{
-        Commands::Summary => {
-            summary(parsed);
+        Commands::Summary{ chart } => {
+            summary(parsed, chart, output);
         },
         Commands::Commands{ n, a } => {
-            top_commands(parsed, n, a);
+            top_commands(parsed, n, a, output);
         },
         Commands::Installs{ n, a } => {
-            top_installs(parsed, n, a);
+            top_installs(parsed, n, a, output);
         },
         Commands::Removes{ n, a } => {
-            top_removes(parsed, n, a);
+            top_removes(parsed, n, a, output);
         },
         Commands::Upgrades{ n, a } => {
-            top_upgrades(parsed, n, a);
+            top_upgrades(parsed, n, a, output);
         },
         Commands::Downgrades{ n, a } => {
-            top_downgrades(parsed, n, a);
+            top_downgrades(parsed, n, a, output);
         },
-        Commands::Package{ package, upgrade_command } => {
-            package_history(parsed, package, upgrade_command);
+        Commands::Package{ package, upgrade_command, format } => {
+            package_history(parsed, package, upgrade_command, format, output);
         },
-        Commands::History{ n, full, no_upgrades, count } => {
-            if full {
-                history_full(parsed, n, no_upgrades);
-            } else if let Err(e) = history_compact(parsed, n, count){
+        Commands::History{ n, full, no_upgrades, count, format } => {
+            if full || format.is_some() {
+                history_full(parsed, n, no_upgrades, format, output);
+            } else if let Err(e) = history_compact(parsed, n, count, output){
                 println!("{:?}", e);
             }
         },
         Commands::Intentional { list }=> {
-            intentional(parsed, list);
+            intentional(parsed, list, output);
+        },
+        Commands::Time { all, year, month, day, hour, chart } => {
+            time(parsed, all, year, month, day, hour, chart, output);
+        },
+        Commands::Report{ format, n, recent } => {
+            report(parsed, format, n, recent);
+        },
+        Commands::Lifespan{ sort, n, a } => {
+            lifespan(parsed, now, sort, n, a, output);
+        },
+        Commands::Dropped{ within, deps } => {
+            dropped(parsed, within, deps, output);
+        },
+        Commands::Leftovers => {
+            leftovers(parsed, output);
+        },
+        Commands::Why{ package } => {
+            why(parsed, package, output);
+        },
+        Commands::State{ at } => {
+            state(parsed, at, output);
+        },
+        Commands::Diff{ from, to } => {
+            diff(parsed, from, to, output);
+        },
+        Commands::Rollback{ at, undo, cache } => {
+            let cache = cache.or(config.cache_dir).unwrap_or(DEFAULT_CACHE.to_string());
+            rollback(parsed, at, undo.map(|undo| undo as usize), &cache, output);
+        },
+        Commands::Bisect{ step, cache } => {
+            let cache = cache.or(config.cache_dir).unwrap_or(DEFAULT_CACHE.to_string());
+            bisect(parsed, step, &cache, output);
         },
-        Commands::Time { all, year, month, day, hour } => {
-            time(parsed, all, year, month, day, hour);
+        Commands::Warnings{ gap } => {
+            warnings(parsed, now, gap, output);
+        },
+        Commands::Regressions => {
+            regressions(parsed, now, output);
+        },
+        Commands::Stale{ days } => {
+            stale(parsed, now, days, output);
+        },
+        Commands::Cadence{ max } => {
+            cadence(parsed, now, max, output);
+        },
+        Commands::Calendar{ year, upgrades } => {
+            calendar(parsed, year, upgrades, output);
+        },
+        Commands::Growth{ jumps, svg } => {
+            growth(parsed, jumps, svg, output);
+        },
+        Commands::Tui => {
+            tui(parsed);
+        },
+        Commands::Metrics{ file } => {
+            metrics(parsed, file);
         },
     }
 }
 
+/// Stops on a mistake of the user with a message on stderr, unlike a panic without a backtrace.
+fn fail(message: impl Display) -> !{
+    eprintln!("Error: {}", message);
+    std::process::exit(1)
+}
+
 type DT = (u16, u8, u8, u8); // date time (y, m, d, h)
 enum Event{
     Command(DT, String), // dt, command
@@ -156,17 +503,117 @@ enum Event{
     Removed(DT, String, String), // dt, package, version
     Upgraded(DT, String, String), // dt, package, version
     Downgraded(DT, String, String), // dt, package, version
+    Marker(DT, i64, Marker), // dt, unix time, what happened
 }
 type Events = Vec<Event>;
 
-fn parse(lines: Vec<String>) -> Vec<Event>{
+/// Log lines that are not package events, but tell something about the transactions around them.
+#[derive(Debug, PartialEq, Eq)]
+enum Marker{
+    Sync, // package databases refreshed
+    FullUpgrade, // pacman started a full system upgrade
+    Started, // transaction started
+    Completed, // transaction completed
+    Failed, // transaction failed or interrupted
+    Pacnew(String), // path of a new config file pacman left next to the old one
+}
+
+impl Event{
+    fn dt(&self) -> DT{
+        match self{
+            Event::Command(dt, _) => *dt,
+            Event::Installed(dt, _, _) => *dt,
+            Event::Removed(dt, _, _) => *dt,
+            Event::Upgraded(dt, _, _) => *dt,
+            Event::Downgraded(dt, _, _) => *dt,
+            Event::Marker(dt, _, _) => *dt,
+        }
+    }
+
+    fn kind(&self) -> &'static str{
+        match self{
+            Event::Command(_, _) => "command",
+            Event::Installed(_, _, _) => "installed",
+            Event::Removed(_, _, _) => "removed",
+            Event::Upgraded(_, _, _) => "upgraded",
+            Event::Downgraded(_, _, _) => "downgraded",
+            Event::Marker(_, _, _) => "marker",
+        }
+    }
+
+    fn package(&self) -> Option<&str>{
+        match self{
+            Event::Command(_, _) | Event::Marker(_, _, _) => None,
+            Event::Installed(_, package, _) => Some(package),
+            Event::Removed(_, package, _) => Some(package),
+            Event::Upgraded(_, package, _) => Some(package),
+            Event::Downgraded(_, package, _) => Some(package),
+        }
+    }
+
+    /// The logged version without parentheses, "old -> new" for upgrades and downgrades.
+    fn version(&self) -> Option<&str>{
+        match self{
+            Event::Command(_, _) | Event::Marker(_, _, _) => None,
+            Event::Installed(_, _, version) |
+            Event::Removed(_, _, version) |
+            Event::Upgraded(_, _, version) |
+            Event::Downgraded(_, _, version) => Some(unparen(version)),
+        }
+    }
+
+    /// Old and new version, taken apart from the logged "(old -> new)" or "(version)".
+    /// A removed package only has an old version, an installed package only a new one.
+    fn versions(&self) -> (Option<&str>, Option<&str>){
+        match self{
+            Event::Command(_, _) | Event::Marker(_, _, _) => (None, None),
+            Event::Installed(_, _, version) => (None, Some(unparen(version))),
+            Event::Removed(_, _, version) => (Some(unparen(version)), None),
+            Event::Upgraded(_, _, version) | Event::Downgraded(_, _, version) => {
+                match unparen(version).split_once(" -> "){
+                    Some((old, new)) => (Some(old), Some(new)),
+                    None => (None, Some(unparen(version))),
+                }
+            },
+        }
+    }
+}
+
+/// The installed packages with their versions after the events, by replaying them.
+fn replay(events: &[Event]) -> BTreeMap<&str, &str>{
+    let mut installed = BTreeMap::new();
+    for event in events{
+        match event{
+            Event::Installed(_, package, _) |
+            Event::Upgraded(_, package, _) |
+            Event::Downgraded(_, package, _) => {
+                if let (_, Some(version)) = event.versions() {
+                    installed.insert(package.as_str(), version);
+                }
+            },
+            Event::Removed(_, package, _) => { installed.remove(package.as_str()); },
+            Event::Command(_, _) | Event::Marker(_, _, _) => { },
+        }
+    }
+    installed
+}
+
+fn unparen(version: &str) -> &str{
+    version.trim_start_matches('(').trim_end_matches(')')
+}
+
+/// The events of the log lines, and the UTC offset in seconds of the last line to tell the current
+/// time in the log's local time.
+fn parse(lines: Vec<String>) -> (Vec<Event>, i64){
     let mut res = Vec::new();
+    let mut offset = 0;
 
     for line in lines {
         let parts = line.split(' ').collect::<Vec<_>>();
         if parts.len() < 4 { continue; }
 
         let dt = if let Ok(dt) = parse_dt(parts[0]) { dt } else { continue; };
+        offset = utc_offset(parts[0]);
 
         if parts[1] == "[PACMAN]" && parts[2] == "Running"{
             let mut command = parts[3..].join(" ");
@@ -184,15 +631,80 @@ fn parse(lines: Vec<String>) -> Vec<Event>{
         } else if parts[1] == "[ALPM]" && parts[2] == "downgraded"{
             let version = parts[4..].join(" ");
             res.push(Event::Downgraded(dt, parts[3].to_string(), version));
+        } else if let Some(marker) = parse_marker(&parts) {
+            res.push(Event::Marker(dt, unix_time(parts[0], dt), marker));
         }
     }
 
-    res
+    (res, offset)
 }
 
-fn summary(events: Events){
-    let nevents = events.len();
-    let mut packages = 0usize;
+fn parse_marker(parts: &[&str]) -> Option<Marker>{
+    let marker = match parts[1..]{
+        ["[PACMAN]", "synchronizing", "package", "lists"] => Marker::Sync,
+        ["[PACMAN]", "starting", "full", "system", "upgrade"] => Marker::FullUpgrade,
+        ["[ALPM]", "transaction", "started"] => Marker::Started,
+        ["[ALPM]", "transaction", "completed"] => Marker::Completed,
+        ["[ALPM]", "transaction", "failed" | "interrupted"] => Marker::Failed,
+        // [ALPM] warning: /etc/locale.gen installed as /etc/locale.gen.pacnew
+        ["[ALPM]", "warning:", _, "installed", "as", path] if path.ends_with(".pacnew") => {
+            Marker::Pacnew(path.to_string())
+        },
+        _ => return None,
+    };
+    Some(marker)
+}
+
+#[derive(Serialize)]
+struct Summary<'a>{
+    first_command: Option<Record<'a>>,
+    packages: usize,
+    events: usize,
+    updates: usize,
+    installs: usize,
+    removes: usize,
+    upgrades: usize,
+    downgrades: usize,
+    commands_per_year: Table<u16>,
+}
+
+struct Metric{
+    name: String,
+    value: String,
+}
+
+impl Tabular for Metric{
+    const HEADER: &'static [&'static str] = &["metric", "value"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![self.name.clone(), self.value.clone()]
+    }
+}
+
+impl Summary<'_>{
+    fn metrics(&self) -> Vec<Metric>{
+        let metric = |name: &str, value: String| Metric{ name: name.to_string(), value };
+        let mut metrics = Vec::new();
+        if let Some(record) = &self.first_command {
+            metrics.push(metric("first_command_date", record.date.clone()));
+            metrics.push(metric("first_command", record.command.unwrap_or("").to_string()));
+        }
+        metrics.push(metric("packages", self.packages.to_string()));
+        metrics.push(metric("events", self.events.to_string()));
+        metrics.push(metric("updates", self.updates.to_string()));
+        metrics.push(metric("installs", self.installs.to_string()));
+        metrics.push(metric("removes", self.removes.to_string()));
+        metrics.push(metric("upgrades", self.upgrades.to_string()));
+        metrics.push(metric("downgrades", self.downgrades.to_string()));
+        for row in &self.commands_per_year.rows{
+            metrics.push(metric(&format!("commands_{}", row.value), row.count.to_string()));
+        }
+        metrics
+    }
+}
+
+fn summarize(events: &Events) -> Summary<'_>{
+    let nevents = events.iter().filter(|e| !matches!(e, Event::Marker(_, _, _))).count();
     let mut updates = 0usize;
     let mut installs = 0usize;
     let mut removes = 0usize;
@@ -201,25 +713,21 @@ fn summary(events: Events){
     let mut last_command_update = false;
     let mut y_map = FreqMap::new();
 
-    if let Some(Event::Command(dt, command)) = events.first() {
-        println!(
-            "First command ran on {}:\n\t\"{}{}{}\"",
-            format_dt(*dt), MAGENTA, command, RESET
-        );
-    }
+    let first_command = match events.iter().find(|e| !matches!(e, Event::Marker(_, _, _))) {
+        Some(event@Event::Command(_, _)) => Some(event),
+        _ => None,
+    };
 
     for event in events {
         match event{
             Event::Command((y, _, _, _), _) => {
                 last_command_update = false;
-                y_map.inc(y);
+                y_map.inc(*y);
             },
             Event::Installed(_, _, _) => {
-                packages += 1;
                 installs += 1;
             },
             Event::Removed(_, _, _) => {
-                packages -= 1;
                 removes += 1;
             },
             Event::Upgraded(_, _, _) => {
@@ -232,101 +740,164 @@ fn summary(events: Events){
             Event::Downgraded(_, _, _) => {
                 downgrades += 1;
             },
+            Event::Marker(_, _, _) => { },
         }
     }
 
-    println!("Packages installed: {}{}{}\n", RED, packages, RESET);
-    println!("Events: {}{}{}", RED, nevents, RESET);
-    println!("Updates: {}{}{}", RED, updates, RESET);
-    println!("Installs: {}{}{}", RED, installs, RESET);
-    println!("Removes: {}{}{}", RED, removes, RESET);
-    println!("Upgrades: {}{}{}", RED, upgrades, RESET);
-    println!("Downgrades: {}{}{}", RED, downgrades, RESET);
+    Summary{
+        first_command: first_command.map(|event| Record::new(event, None)),
+        // replayed, like the running count of the transactions
+        packages: replay(events).len(),
+        events: nevents,
+        updates,
+        installs,
+        removes,
+        upgrades,
+        downgrades,
+        commands_per_year: y_map.table(100, false),
+    }
+}
+
+fn summary(events: Events, chart: bool, output: Output){
+    let summary = summarize(&events);
+    if output.structured() {
+        print_structured(output, &summary, &summary.metrics());
+        return;
+    }
+
+    let c = colours();
+    if let Some(Record{ dt, command: Some(command), .. }) = &summary.first_command {
+        println!(
+            "First command ran on {}:\n\t\"{}{}{}\"",
+            format_dt(*dt), c.command, command, c.reset
+        );
+    }
+    println!("Packages installed: {}{}{}\n", c.count, summary.packages, c.reset);
+    println!("Events: {}{}{}", c.count, summary.events, c.reset);
+    println!("Updates: {}{}{}", c.count, summary.updates, c.reset);
+    println!("Installs: {}{}{}", c.count, summary.installs, c.reset);
+    println!("Removes: {}{}{}", c.count, summary.removes, c.reset);
+    println!("Upgrades: {}{}{}", c.count, summary.upgrades, c.reset);
+    println!("Downgrades: {}{}{}", c.count, summary.downgrades, c.reset);
     println!();
-    print_map(y_map, "Commands", 100, false);
+    if chart {
+        print_bars(&summary.commands_per_year, "Commands");
+    } else {
+        print_table(&summary.commands_per_year, "Commands");
+    }
 }
 
-macro_rules! impl_top{
-    ($fn_name:ident, $enum_match:ident, $msg:expr, $obj:expr) => {
-        fn $fn_name(events: Events, n: usize, all: bool){
-            let mut map = FreqMap::new();
-            for event in events{
-                if let Event::$enum_match(_, prog, ..) = event {
-                    map.inc(prog);
-                }
-            }
+/// How often each package, or each command for commands, shows up in events of a kind.
+fn frequencies<'a>(events: &'a Events, kind: &str) -> FreqMap<&'a str>{
+    let mut map = FreqMap::new();
+    for event in events{
+        if event.kind() != kind { continue; }
+        match event{
+            Event::Command(_, command) => map.inc(command.as_str()),
+            other => map.inc(other.package().unwrap_or("")),
+        }
+    }
+    map
+}
 
+macro_rules! impl_top{
+    ($fn_name:ident, $kind:expr, $msg:expr, $obj:expr) => {
+        fn $fn_name(events: Events, n: usize, all: bool, output: Output){
+            let map = frequencies(&events, $kind);
             let n = if all { map.len() } else { n };
-            print_map(map, $msg, n, true);
+            let table = map.table(n, true);
+            if output.structured() {
+                print_structured(output, &table, &table.rows);
+                return;
+            }
+            print_table(&table, $msg);
             if all {
-                println!("Number of {}: {RED}{BOLD}{n}{RESET}", $obj);
+                let c = colours();
+                println!("Number of {}: {}{n}{}", $obj, c.total, c.reset);
             }
         }
     };
 }
 
-impl_top!(top_commands, Command, "Commands", "commands");
-impl_top!(top_installs, Installed, "Installs", "packages");
-impl_top!(top_removes, Removed, "Removes", "packages");
-impl_top!(top_upgrades, Upgraded, "Upgrades", "packages");
-impl_top!(top_downgrades, Downgraded, "Downgrades", "packages");
+impl_top!(top_commands, "command", "Commands", "commands");
+impl_top!(top_installs, "installed", "Installs", "packages");
+impl_top!(top_removes, "removed", "Removes", "packages");
+impl_top!(top_upgrades, "upgraded", "Upgrades", "packages");
+impl_top!(top_downgrades, "downgraded", "Downgrades", "packages");
 
-fn package_history(events: Events, target_package: String, upgrade_command: bool){
-    let mut last_command = String::new();
-    for event in events{
-        match event{
-            // date time (y, m, d, h)
-            Event::Command(_, command) => {
-                last_command = command;
-            },
-            Event::Installed(dt, package, version) => {
-                if target_package != package { continue; }
-                println!(
-                    "{} - {}{}installed{} version {}{}{}{}{} with: {}{}{}{}",
-                    format_dt(dt), BOLD, GREEN, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
-                    ITALIC, MAGENTA, last_command, RESET
-                );
-            },
-            Event::Removed(dt, package, version) => {
-                if target_package != package { continue; }
-                println!(
-                    "{} - {}{}removed{} version {}{}{}{}{} with: {}{}{}{}",
-                    format_dt(dt), BOLD, RED, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
-                    ITALIC, MAGENTA, last_command, RESET
-                );
-            },
-            Event::Upgraded(dt, package, version) => {
-                if target_package != package { continue; }
-                println!(
-                    "{} - {}upgraded{} {}from{} version{} to{} {}{}{}{}{}",
-                    format_dt(dt), GREEN, RESET,
-                    FAINT, RESET, FAINT, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
-                );
-                if upgrade_command {
-                    println!(
-                        " with: {}{}{}{}",
-                        ITALIC, MAGENTA, last_command, RESET
-                    );
-                }
-            },
-            Event::Downgraded(dt, package, version) => {
-                if target_package != package { continue; }
+fn package_history(
+    events: Events, target_package: String, upgrade_command: bool, template: Option<Template>,
+    output: Output
+){
+    let mut last_command = None;
+    let mut records = Vec::new();
+    for event in &events{
+        if let Event::Command(_, command) = event {
+            last_command = Some(command.as_str());
+            continue;
+        }
+        if event.package() != Some(target_package.as_str()) { continue; }
+        if output.structured() {
+            records.push(Record::new(event, last_command));
+        } else if let Some(template) = &template {
+            println!("{}", template.render(event, last_command));
+        } else {
+            print_package_event(event, upgrade_command, last_command.unwrap_or(""));
+        }
+    }
+    print_structured(output, &records, &records);
+}
+
+fn print_package_event(event: &Event, upgrade_command: bool, last_command: &str){
+    let c = colours();
+    match event{
+        // date time (y, m, d, h)
+        Event::Command(_, _) | Event::Marker(_, _, _) => { },
+        Event::Installed(dt, _, version) => {
+            println!(
+                "{} - {}installed{} version {}{}{} with: {}{}{}{}",
+                format_dt(*dt), c.install, c.reset,
+                c.version, version, c.reset,
+                c.italic, c.command, last_command, c.reset
+            );
+        },
+        Event::Removed(dt, _, version) => {
+            println!(
+                "{} - {}removed{} version {}{}{} with: {}{}{}{}",
+                format_dt(*dt), c.remove, c.reset,
+                c.version, version, c.reset,
+                c.italic, c.command, last_command, c.reset
+            );
+        },
+        Event::Upgraded(dt, _, version) => {
+            println!(
+                "{} - {}upgraded{} {}from{} version{} to{} {}{}{}",
+                format_dt(*dt), c.upgrade, c.reset,
+                c.faint, c.reset, c.faint, c.reset,
+                c.version, version, c.reset,
+            );
+            if upgrade_command {
                 println!(
-                    "{} - {}{}downgraded{} {}from{} version{} to{} {}{}{}{}{} with: {}{}{}{}",
-                    format_dt(dt), RED, UNDERLINED, RESET,
-                    FAINT, RESET, FAINT, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
-                    ITALIC, MAGENTA, last_command, RESET
+                    " with: {}{}{}{}",
+                    c.italic, c.command, last_command, c.reset
                 );
-            },
-        }
+            }
+        },
+        Event::Downgraded(dt, _, version) => {
+            println!(
+                "{} - {}downgraded{} {}from{} version{} to{} {}{}{} with: {}{}{}{}",
+                format_dt(*dt), c.downgrade, c.reset,
+                c.faint, c.reset, c.faint, c.reset,
+                c.version, version, c.reset,
+                c.italic, c.command, last_command, c.reset
+            );
+        },
     }
 }
 
-fn history_full(events: Events, n: usize, no_upgrades: bool) {
+fn history_full(
+    events: Events, n: usize, no_upgrades: bool, template: Option<Template>, output: Output
+) {
     let mut filtered = Vec::new();
     let mut m = 0;
     let mut last_ok = false;
@@ -350,6 +921,7 @@ fn history_full(events: Events, n: usize, no_upgrades: bool) {
                     last_ok = false;
                 }
             },
+            Event::Marker(_, _, _) => { },
             other => {
                 if m >= n { continue; }
                 last_ok = true;
@@ -358,192 +930,312 @@ fn history_full(events: Events, n: usize, no_upgrades: bool) {
             }
         }
     }
-    for event in filtered.into_iter().rev()
+    filtered.reverse();
+    if output.structured() {
+        let mut last_command = None;
+        let mut records = Vec::new();
+        for event in &filtered{
+            if let Event::Command(_, command) = event {
+                last_command = Some(command.as_str());
+            }
+            records.push(Record::new(event, last_command));
+        }
+        print_structured(output, &records, &records);
+        return;
+    }
+    if let Some(template) = template {
+        // the command is part of every line, so commands don't get a line of their own
+        let mut last_command = None;
+        for event in &filtered{
+            match event{
+                Event::Command(_, command) => last_command = Some(command.as_str()),
+                Event::Marker(_, _, _) => { },
+                _ => println!("{}", template.render(event, last_command)),
+            }
+        }
+        return;
+    }
+    let c = colours();
+    for event in filtered
     {
         match event{
             // date time (y, m, d, h)
             Event::Command(dt, command) => {
                 println!(
                     "{} - {}{}command{}: {}{}{}{}",
-                    format_dt(dt), BOLD, MAGENTA, RESET,
-                    BOLD, ITALIC, command, RESET,
+                    format_dt(dt), c.bold, c.command, c.reset,
+                    c.bold, c.italic, command, c.reset,
                 );
             },
             Event::Installed(dt, package, version) => {
                 println!(
-                    "{} - {}{}installed{} {}{}{} version {}{}{}{}{}",
-                    format_dt(dt), BOLD, GREEN, RESET,
-                    BOLD, package, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
+                    "{} - {}installed{} {}{}{} version {}{}{}",
+                    format_dt(dt), c.install, c.reset,
+                    c.package, package, c.reset,
+                    c.version, version, c.reset,
                 );
             },
             Event::Removed(dt, package, version) => {
                 println!(
-                    "{} - {}{}removed{} {}{}{} version {}{}{}{}{}",
-                    format_dt(dt), BOLD, RED, RESET,
-                    BOLD, package, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
+                    "{} - {}removed{} {}{}{} version {}{}{}",
+                    format_dt(dt), c.remove, c.reset,
+                    c.package, package, c.reset,
+                    c.version, version, c.reset,
                 );
             },
             Event::Upgraded(dt, package, version) => {
                 println!(
-                    "{} - {}upgraded{} {}{}{} {}from{} version{} to{} {}{}{}{}{}",
-                    format_dt(dt), GREEN, RESET,
-                    BOLD, package, RESET,
-                    FAINT, RESET, FAINT, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
+                    "{} - {}upgraded{} {}{}{} {}from{} version{} to{} {}{}{}",
+                    format_dt(dt), c.upgrade, c.reset,
+                    c.package, package, c.reset,
+                    c.faint, c.reset, c.faint, c.reset,
+                    c.version, version, c.reset,
                 );
             },
             Event::Downgraded(dt, package, version) => {
                 println!(
-                    "{} - {}{}downgraded{} {}{}{} {}from{} version{} to{} {}{}{}{}{}",
-                    format_dt(dt), RED, UNDERLINED, RESET,
-                    BOLD, package, RESET,
-                    FAINT, RESET, FAINT, RESET,
-                    FAINT, ITALIC, CYAN, version, RESET,
+                    "{} - {}downgraded{} {}{}{} {}from{} version{} to{} {}{}{}",
+                    format_dt(dt), c.downgrade, c.reset,
+                    c.package, package, c.reset,
+                    c.faint, c.reset, c.faint, c.reset,
+                    c.version, version, c.reset,
                 );
             },
+            Event::Marker(_, _, _) => { },
         }
     }
 }
 
-fn history_compact(events: Events, mut n: usize, count: bool) -> Result<(), fmt::Error> {
-    let mut named = Vec::new();
-    let mut unnamed = Vec::new();
-    let mut install: Vec<String> = Vec::new();
-    let mut remove: Vec<String> = Vec::new();
-    let mut upgrade: Vec<String> = Vec::new();
-    let mut downgrade: Vec<String> = Vec::new();
-    let mut strings = Vec::new();
-    let mut packages = 0;
-    for event in &events {
-        match event{
-            Event::Installed(_, _, _) => { packages += 1; },
-            Event::Removed(_, _, _) => { packages -= 1; },
-            _ => { }
+/// A command together with the package events that followed it.
+#[derive(Serialize)]
+struct Transaction{
+    #[serde(rename = "date", serialize_with = "serialize_dt")]
+    dt: DT,
+    command: String,
+    #[serde(rename = "installed")]
+    install: Vec<String>,
+    #[serde(rename = "removed")]
+    remove: Vec<String>,
+    #[serde(rename = "upgraded")]
+    upgrade: Vec<String>,
+    #[serde(rename = "downgraded")]
+    downgrade: Vec<String>,
+    // packages that are mentioned in the command
+    named: Vec<String>,
+    // packages that came along, dependencies for example
+    unnamed: Vec<String>,
+    // amount of installed packages after the transaction
+    packages: i32,
+}
+
+impl Tabular for Transaction{
+    const HEADER: &'static [&'static str] = &[
+        "date", "command", "installed", "removed", "upgraded", "downgraded",
+        "named", "unnamed", "packages",
+    ];
+
+    // package lists are separated by spaces, like on the command line
+    fn fields(&self) -> Vec<String>{
+        vec![
+            iso_dt(self.dt),
+            self.command.clone(),
+            self.install.join(" "),
+            self.remove.join(" "),
+            self.upgrade.join(" "),
+            self.downgrade.join(" "),
+            self.named.join(" "),
+            self.unnamed.join(" "),
+            self.packages.to_string(),
+        ]
+    }
+}
+
+impl Transaction{
+    fn new(dt: DT, command: String, packages: i32) -> Self{
+        Self{
+            dt,
+            command,
+            install: Vec::new(),
+            remove: Vec::new(),
+            upgrade: Vec::new(),
+            downgrade: Vec::new(),
+            named: Vec::new(),
+            unnamed: Vec::new(),
+            packages,
         }
     }
-    for event in events.into_iter().rev(){
+
+    fn split_named(&mut self){
+        let words = self.command.split(' ').collect::<Vec<_>>();
+        for package in self.install.iter()
+            .chain(self.remove.iter())
+            .chain(self.upgrade.iter())
+            .chain(self.downgrade.iter())
+        {
+            if words.contains(&package.as_ref()) {
+                self.named.push(package.to_string());
+            } else {
+                self.unnamed.push(package.to_string());
+            }
+        }
+    }
+
+    fn singular(&self) -> bool{
+        self.install.len().min(1) +
+        self.remove.len().min(1) +
+        // self.upgrade.len().min(1) +
+        self.downgrade.len().min(1) < 2
+    }
+
+    fn shown(&self, count: bool) -> bool{
+        let (hu, hd, hi, hr) = (
+            !self.upgrade.is_empty(), !self.downgrade.is_empty(),
+            !self.install.is_empty(), !self.remove.is_empty()
+        );
+        if count {
+            hi || hr || hd
+        } else {
+            !self.singular() || (!hu && !self.named.is_empty())
+        }
+    }
+
+    fn compact_string(&self, count: bool) -> Result<String, fmt::Error>{
+        let c = colours();
+        let Transaction{ dt, command, install, remove, upgrade, downgrade, named, unnamed, .. } = self;
+        let dt = *dt;
+        let mut string = String::new();
+        let (hu, hd, hi, hr) = (
+            !upgrade.is_empty(), !downgrade.is_empty(),
+            !install.is_empty(), !remove.is_empty()
+        );
+        if count {
+            write!(string, "{} - ", format_dt(dt))?;
+            let diff = install.len() as i32 - remove.len() as i32;
+            // green for negative because removing is good
+            let (dcol, dchar) = match diff.cmp(&0) {
+                std::cmp::Ordering::Less => (&c.decrease, '-'),
+                std::cmp::Ordering::Equal => (&c.unchanged, '+'),
+                std::cmp::Ordering::Greater => (&c.increase, '+'),
+            };
+            write!(string,
+                "{}{:>4}{} -> {:<4} ",
+                dcol, format!("{dchar}{}", diff.abs()), c.reset, self.packages
+            )?;
+            match (hi, hr, hu | hd) {
+                (true, false, false) => write!(string, "{}install{}", c.install, c.reset),
+                (false, true, false) => write!(string, "{}remove{} ", c.remove, c.reset),
+                _ => write!(string, "{}{}complex{}", c.bold, c.command, c.reset),
+            }?;
+            if !named.is_empty() {
+                write!(string, " {}", named.vec_string_inner())?;
+            } else {
+                write!(string, " {}{}{}", c.command, command, c.reset)?;
+            }
+        } else if self.singular() {
+            write!(string, "{} - ", format_dt(dt))?;
+            if hi { write!(string, "{}install{} ", c.install, c.reset)?; }
+            if hr { write!(string, "{}remove{} ", c.remove, c.reset)?; }
+            if hu { write!(string, "{}{}upgrade{} ", c.bold, c.upgrade, c.reset)?; }
+            if hd { write!(string, "{}{}downgrade{} ", c.bold, c.downgrade, c.reset)?; }
+            write!(string, "{}", named.vec_string_inner())?;
+            if !unnamed.is_empty() {
+                write!(string, ", {}{}{}", c.faint, unnamed.vec_string_inner(), c.reset)?;
+            }
+        } else {
+            write!(string, "{} - ", format_dt(dt))?;
+            write!(string, "{}{}complex{} ", c.bold, c.command, c.reset)?;
+            write!(string, "{}{}{}{}",
+                c.upgrade, c.underlined, upgrade.vec_string_inner(), c.reset)?;
+            if hu && (hd || hi || hr) { write!(string, ", ")?; }
+            write!(string, "{}{}{}",
+                c.downgrade, downgrade.vec_string_inner(), c.reset)?;
+            if hd && (hi || hr) { write!(string, ", ")?; }
+            write!(string, "{}{}{}", c.install, install.vec_string_inner(), c.reset)?;
+            if hi && hr { write!(string, ", ")?; }
+            write!(string, "{}{}{}", c.remove, remove.vec_string_inner(), c.reset)?;
+            write!(string, ", ")?;
+            write!(string, "{}{}{}", c.command, command, c.reset)?;
+        }
+        writeln!(string)?;
+        Ok(string)
+    }
+}
+
+/// Groups the events per command that caused them.
+/// Events before the first command are not part of any transaction.
+fn transactions(events: &Events) -> Vec<Transaction>{
+    let mut res: Vec<Transaction> = Vec::new();
+    // the installed packages like replay() sees them, a rotated log can start with upgrades and
+    // removals of packages it never saw installed
+    let mut installed: HashSet<&str> = HashSet::new();
+    for event in events{
         match event{
             Event::Command(dt, command) => {
-                let singular =
-                    install.len().min(1) +
-                    remove.len().min(1) +
-                    // upgrade.len().min(1) +
-                    downgrade.len().min(1) < 2;
-                let words = command.split(' ').collect::<Vec<_>>();
-                for package in install.iter()
-                    .chain(remove.iter())
-                    .chain(upgrade.iter())
-                    .chain(downgrade.iter())
-                {
-                    let package = package.to_string();
-                    if words.contains(&package.as_ref()) {
-                        named.push(package);
-                    } else {
-                        unnamed.push(package);
-                    }
-                }
-                let mut string = String::new();
-                let mut done_something = false;
-                let (hu, hd, hi, hr) = (
-                    !upgrade.is_empty(), !downgrade.is_empty(),
-                    !install.is_empty(), !remove.is_empty()
-                );
-                if count && (hi || hr || hd) {
-                    write!(string, "{} - ", format_dt(dt))?;
-                    let diff = install.len() as i32 - remove.len() as i32;
-                    // green for negative because removing is good
-                    let (dcol, dchar) = match diff.cmp(&0) {
-                        std::cmp::Ordering::Less => (GREEN, '-'),
-                        std::cmp::Ordering::Equal => (YELLOW, '+'),
-                        std::cmp::Ordering::Greater => (RED, '+'),
-                    };
-                    write!(string,
-                        "{BOLD}{dcol}{:>4}{RESET} -> {:<4} ",
-                        format!("{dchar}{}", diff.abs()), packages
-                    )?;
-                    packages -= diff;
-                    write!(string, "{BOLD}")?;
-                    match (hi, hr, hu | hd) {
-                        (true, false, false) => write!(string, "{GREEN}install{RESET}"),
-                        (false, true, false) => write!(string, "{RED}remove{RESET} "),
-                        _ => write!(string, "{MAGENTA}complex{RESET}"),
-                    }?;
-                    write!(string, "{RESET}")?;
-                    if !named.is_empty() {
-                        write!(string, " {}", named.vec_string_inner())?;
-                    } else {
-                        write!(string, " {MAGENTA}{}{RESET}", command)?;
-                    }
-                    done_something = true;
-                } else if singular && !hu && !named.is_empty() {
-                    write!(string, "{} - ", format_dt(dt))?;
-                    if hi { write!(string, "{}{}install{} ", BOLD, GREEN, RESET)?; }
-                    if hr { write!(string, "{}{}remove{} ", BOLD, RED, RESET)?; }
-                    if hu { write!(string, "{}{}upgrade{} ", BOLD, GREEN, RESET)?; }
-                    if hd { write!(string, "{}{}{}downgrade{} ", BOLD, UNDERLINED, RED, RESET)?; }
-                    write!(string, "{}", named.vec_string_inner())?;
-                    if !unnamed.is_empty() {
-                        write!(string, ", {}{}{}", FAINT, unnamed.vec_string_inner(), RESET)?;
-                    }
-                    done_something = true;
-                } else if !singular {
-                    write!(string, "{} - ", format_dt(dt))?;
-                    write!(string, "{}{}complex{} ", BOLD, MAGENTA, RESET)?;
-                    write!(string, "{}{}{}{}",
-                        GREEN, UNDERLINED, upgrade.vec_string_inner(), RESET)?;
-                    if hu && (hd || hi || hr) { write!(string, ", ")?; }
-                    write!(string, "{}{}{}{}",
-                        RED, UNDERLINED, downgrade.vec_string_inner(), RESET)?;
-                    if hd && (hi || hr) { write!(string, ", ")?; }
-                    write!(string, "{}{}{}", GREEN, install.vec_string_inner(), RESET)?;
-                    if hi && hr { write!(string, ", ")?; }
-                    write!(string, "{}{}{}", RED, remove.vec_string_inner(), RESET)?;
-                    write!(string, ", ")?;
-                    write!(string, "{}{}{}", MAGENTA, command, RESET)?;
-                    done_something = true;
-                }
-                if done_something {
-                    writeln!(string)?;
-                    strings.push(string);
-                    n -= 1;
-                    if n == 0 { break; }
-                }
-                named.clear();
-                unnamed.clear();
-                install.clear();
-                remove.clear();
-                upgrade.clear();
-                downgrade.clear();
-            },
-            Event::Installed(_, package, _) => {
-                install.push(package.to_string());
-            },
-            Event::Removed(_, package, _) => {
-                remove.push(package.to_string());
-            },
-            Event::Upgraded(_, package, _) => {
-                upgrade.push(package.to_string());
-            },
-            Event::Downgraded(_, package, _) => {
-                downgrade.push(package.to_string());
+                res.push(Transaction::new(*dt, command.clone(), installed.len() as i32));
+                continue;
             },
+            Event::Installed(_, package, _) |
+            Event::Upgraded(_, package, _) |
+            Event::Downgraded(_, package, _) => { installed.insert(package); },
+            Event::Removed(_, package, _) => { installed.remove(package.as_str()); },
+            Event::Marker(_, _, _) => { }
+        }
+        let Some(t) = res.last_mut() else { continue; };
+        t.packages = installed.len() as i32;
+        match event{
+            Event::Installed(_, package, _) => t.install.push(package.clone()),
+            Event::Removed(_, package, _) => t.remove.push(package.clone()),
+            Event::Upgraded(_, package, _) => t.upgrade.push(package.clone()),
+            Event::Downgraded(_, package, _) => t.downgrade.push(package.clone()),
+            Event::Command(_, _) | Event::Marker(_, _, _) => { },
         }
     }
-    for string in strings.into_iter().rev(){
-        print!("{}", string);
+    for t in res.iter_mut(){
+        t.split_named();
+    }
+    res
+}
+
+fn history_compact(events: Events, n: usize, count: bool, output: Output)
+    -> Result<(), fmt::Error>
+{
+    let shown = transactions(&events).into_iter()
+        .filter(|t| t.shown(count))
+        .collect::<Vec<_>>();
+    let shown = &shown[shown.len().saturating_sub(n)..];
+    if output.structured() {
+        print_structured(output, &shown, shown);
+        return Ok(());
+    }
+    for t in shown{
+        print!("{}", t.compact_string(count)?);
     }
     Ok(())
 }
 
-fn intentional(events: Events, list: bool) {
+#[derive(Serialize)]
+struct IntentionalPackage<'a>{
+    package: &'a str,
+    never_removed: bool,
+}
+
+impl Tabular for IntentionalPackage<'_>{
+    const HEADER: &'static [&'static str] = &["package", "never_removed"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![self.package.to_string(), self.never_removed.to_string()]
+    }
+}
+
+/// The packages that are installed because a command named them, and the packages a command
+/// ever named for removal.
+fn intentional_packages(events: &Events) -> (HashSet<String>, HashSet<String>){
     let mut install: Vec<String> = Vec::new();
     let mut remove: Vec<String> = Vec::new();
     let mut upgrade: Vec<String> = Vec::new();
     let mut downgrade: Vec<String> = Vec::new();
     let mut irlines = Vec::new();
-    for event in events.into_iter().rev(){
+    for event in events.iter().rev(){
         match event{
             Event::Command(_, command) => {
                 let words = command.split(' ').collect::<Vec<_>>();
@@ -578,6 +1270,7 @@ fn intentional(events: Events, list: bool) {
             Event::Downgraded(_, package, _) => {
                 downgrade.push(package.to_string());
             },
+            Event::Marker(_, _, _) => { },
         }
     }
     let mut current = HashSet::new();
@@ -590,6 +1283,20 @@ fn intentional(events: Events, list: bool) {
             removed.insert(ir.1);
         }
     }
+    (current, removed)
+}
+
+fn intentional(events: Events, list: bool, output: Output) {
+    let (current, removed) = intentional_packages(&events);
+    if output.structured() {
+        let mut packages = current.iter().map(|p| IntentionalPackage{
+            package: p,
+            never_removed: !removed.contains(p),
+        }).collect::<Vec<_>>();
+        packages.sort_unstable_by_key(|p| p.package);
+        print_structured(output, &packages, &packages);
+        return;
+    }
     let cs = term_size::dimensions().unwrap_or((0, 0)).0;
     let l = current.iter().map(|p| p.chars().count()).max().unwrap_or(0);
     let l = if cs != 0 {
@@ -598,6 +1305,7 @@ fn intentional(events: Events, list: bool) {
     } else {
         0
     };
+    let colours = colours();
     let mut c = 0;
     for package in current.iter(){
         if list {
@@ -611,19 +1319,77 @@ fn intentional(events: Events, list: bool) {
             c += l;
         }
         if !removed.contains(package) {
-            print!("{}", BOLD);
+            print!("{}", colours.bold);
         } else {
-            print!("{}", RESET);
+            print!("{}", colours.reset);
         }
         print!("{:<l$}", package);
     }
 }
 
-fn time(events: Events, all: bool, year: bool, month: bool, day: bool, hour: bool){
-    let (year, month, day, hour) = (
-        year | all | !(month | day | hour), month | all, day | all, hour | all
-    );
+#[derive(Serialize)]
+struct TimeTables{
+    commands: Table<u16>,
+    installs: Table<u16>,
+    removes: Table<u16>,
+    upgrades: Table<u16>,
+    downgrades: Table<u16>,
+}
+
+/// A row of a time table, flattened so all time tables fit in one csv table.
+struct TimeRow<'a>{
+    period: &'static str,
+    category: &'static str,
+    row: &'a Row<u16>,
+}
+
+impl Tabular for TimeRow<'_>{
+    const HEADER: &'static [&'static str] = &["period", "category", "value", "count", "percent"];
+
+    fn fields(&self) -> Vec<String>{
+        let mut fields = vec![self.period.to_string(), self.category.to_string()];
+        fields.extend(self.row.fields());
+        fields
+    }
+}
 
+#[derive(Serialize)]
+struct TimeStats{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_year: Option<TimeTables>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_month: Option<TimeTables>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_day: Option<TimeTables>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_hour: Option<TimeTables>,
+}
+
+impl TimeStats{
+    fn rows(&self) -> Vec<TimeRow<'_>>{
+        let mut rows = Vec::new();
+        let periods = [
+            ("year", &self.per_year), ("month", &self.per_month),
+            ("day", &self.per_day), ("hour", &self.per_hour),
+        ];
+        for (period, tables) in periods{
+            let Some(t) = tables else { continue; };
+            let categories = [
+                ("commands", &t.commands), ("installs", &t.installs), ("removes", &t.removes),
+                ("upgrades", &t.upgrades), ("downgrades", &t.downgrades),
+            ];
+            for (category, table) in categories{
+                for row in &table.rows{
+                    rows.push(TimeRow{ period, category, row });
+                }
+            }
+        }
+        rows
+    }
+}
+
+/// Time tables for the selected periods, the others are left out.
+fn time_stats(events: &Events, year: bool, month: bool, day: bool, hour: bool) -> TimeStats{
     let fmn = FreqMap::new;
     let ma = || [fmn(), fmn(), fmn(), fmn()];
     let [mut cy, mut cm, mut cd, mut ch] = ma();
@@ -641,48 +1407,79 @@ fn time(events: Events, all: bool, year: bool, month: bool, day: bool, hour: boo
     };
 
     for event in events{
-        match event {
+        match *event {
             Event::Command(dt, _) => inc(&mut cy, &mut cm, &mut cd, &mut ch, dt),
             Event::Installed(dt, _, _) => inc(&mut iy, &mut im, &mut id, &mut ih, dt),
             Event::Removed(dt, _, _) => inc(&mut ry, &mut rm, &mut rd, &mut rh, dt),
             Event::Upgraded(dt, _, _) => inc(&mut uy, &mut um, &mut ud, &mut uh, dt),
             Event::Downgraded(dt, _, _) => inc(&mut dy, &mut dm, &mut dd, &mut dh, dt),
+            Event::Marker(_, _, _) => { },
         }
     }
 
     type FM = FreqMap<u16>;
-    let pm = |condition: bool, c: FM, i: FM, r: FM, u: FM, d: FM, n: usize, msg: &str| {
-        if !condition { return; }
+    let tt = |condition: bool, c: FM, i: FM, r: FM, u: FM, d: FM, n: usize| {
+        condition.then(|| TimeTables{
+            commands: c.table(n, false),
+            installs: i.table(n, false),
+            removes: r.table(n, false),
+            upgrades: u.table(n, false),
+            downgrades: d.table(n, false),
+        })
+    };
+    TimeStats{
+        per_year: tt(year, cy, iy, ry, uy, dy, 100),
+        per_month: tt(month, cm, im, rm, um, dm, 12),
+        per_day: tt(day, cd, id, rd, ud, dd, 31),
+        per_hour: tt(hour, ch, ih, rh, uh, dh, 24),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn time(
+    events: Events, all: bool, year: bool, month: bool, day: bool, hour: bool, chart: bool,
+    output: Output
+){
+    let (year, month, day, hour) = (
+        year | all | !(month | day | hour), month | all, day | all, hour | all
+    );
+    let stats = time_stats(&events, year, month, day, hour);
+    if output.structured() {
+        print_structured(output, &stats, &stats.rows());
+        return;
+    }
+
+    let print = if chart { print_bars::<u16> } else { print_table::<u16> };
+    // the axis has a character per slot
+    let pt = |tables: &Option<TimeTables>, msg: &str, slots: &[u16], axis: &str| {
+        let Some(t) = tables else { return; };
         println!(" {}\n", msg);
-        print_map(c, "Commands", n, false);
-        print_map(i, "Installs", n, false);
-        print_map(r, "Removes", n, false);
-        print_map(u, "Upgrades", n, false);
-        print_map(d, "Downgrades", n, false);
+        if chart && !slots.is_empty() {
+            print_sparklines(t, slots, axis);
+        }
+        print(&t.commands, "Commands");
+        print(&t.installs, "Installs");
+        print(&t.removes, "Removes");
+        print(&t.upgrades, "Upgrades");
+        print(&t.downgrades, "Downgrades");
         println!();
     };
 
-    pm(year, cy, iy, ry, uy, dy, 100, "- Per year -");
-    pm(month, cm, im, rm, um, dm, 12, "- Per month -");
-    pm(day, cd, id, rd, ud, dd, 31, "- Per day -");
-    pm(hour, ch, ih, rh, uh, dh, 24, "- Per hour -");
+    let months = (1..=12).collect::<Vec<_>>();
+    let hours = (0..24).collect::<Vec<_>>();
+    pt(&stats.per_year, "- Per year -", &[], "");
+    pt(&stats.per_month, "- Per month -", &months, "JFMAMJJASOND");
+    pt(&stats.per_day, "- Per day -", &[], "");
+    pt(&stats.per_hour, "- Per hour -", &hours, "0     6     12    18    ");
 }
 
-fn print_map<T: Display + PartialEq + Eq + PartialOrd + Hash>(
-    map: FreqMap<T>, msg: &str, n: usize, sort_by_freq: bool
-){
-    let total = map.total();
-    println!("{}: {}{}{}{}", msg, BOLD, RED, total, RESET);
-    let vec = if sort_by_freq {
-        map.sorted_by_freq()
-    } else {
-        map.sorted_by_key()
-    };
-    for (to_display, freq) in vec.into_iter().take(n){
+fn print_table<T: Display>(table: &Table<T>, msg: &str){
+    let c = colours();
+    println!("{}: {}{}{}", msg, c.total, table.total, c.reset);
+    for Row{ value, count, percent } in &table.rows{
         println!(
-            "\t{}{}{: >2}{}: {}{}{} times ({}{:.2}%{})",
-            BOLD, GREEN, to_display, RESET, RED, freq, RESET, YELLOW,
-            freq as f32 / total as f32 * 100.0, RESET,
+            "\t{}{: >2}{}: {}{}{} times ({}{:.2}%{})",
+            c.key, value, c.reset, c.count, count, c.reset, c.percent, percent, c.reset,
         );
     }
 }
@@ -740,10 +1537,110 @@ fn parse_dt(s: &str) -> Result<(u16, u8, u8, u8), ParseIntError>{
     Ok((year, month, day, hour))
 }
 
-fn format_dt((y, m, d, h): DT) -> String {
-    format!(
-        "{}{}/{}{:0>2}{}/{}{:0>2} {}{:0>2}{}:{}{}00{}",
-        y, FAINT, RESET, m, FAINT, RESET, d, FAINT, h, BLACK, RESET, FAINT, RESET
-    )
+fn time_field(s: &str, from: usize, to: usize) -> i64{
+    s.get(from..to).and_then(|f| f.parse::<i64>().ok()).unwrap_or(0)
+}
+
+/// The UTC offset in seconds of a logged date time like `[2023-06-30T02:12:34+0200]`.
+/// Old logs with dates like `[2019-01-01 10:11]` have none, they are taken as UTC.
+fn utc_offset(s: &str) -> i64{
+    let offset = time_field(s, 21, 23) * 3600 + time_field(s, 23, 25) * 60;
+    match s.get(20..21){
+        Some("+") => offset,
+        Some("-") => -offset,
+        _ => 0,
+    }
+}
+
+/// Seconds since the epoch of a logged date time like `[2023-06-30T02:12:34+0200]`.
+fn unix_time(s: &str, (y, m, d, h): DT) -> i64{
+    let minute = time_field(s, 15, 17);
+    let second = if s.get(17..18) == Some(":") { time_field(s, 18, 20) } else { 0 };
+    let days = days_from_civil(y as i64, m as i64, d as i64);
+    days * 86400 + h as i64 * 3600 + minute * 60 + second - utc_offset(s)
+}
+
+/// Hours since the epoch, as logged, good enough for durations and time axes.
+fn hours((y, m, d, h): DT) -> i64{
+    days_from_civil(y as i64, m as i64, d as i64) * 24 + h as i64
+}
+
+/// The current time in the same hours as `hours`: the local time of the log, with the UTC offset
+/// of its last line.
+fn hours_now(offset: i64) -> i64{
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    (seconds + offset).div_euclid(3600)
+}
+
+/// A duration like `2y 31d`, `12d 5h` or `7h`.
+fn format_hours(hours: i64) -> String{
+    let (days, hours) = (hours / 24, hours % 24);
+    if days >= 365 {
+        format!("{}y {}d", days / 365, days % 365)
+    } else if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else {
+        format!("{}h", hours)
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64{
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date time with the configured date format, the date digits stand out from the rest.
+fn format_dt(dt: DT) -> String {
+    let c = colours();
+    let mut string = String::new();
+    for (digits, part) in date_parts(dt, date_format()){
+        if digits {
+            string.push_str(&part);
+        } else {
+            string.push_str(&format!("{}{}{}", c.date, part, c.reset));
+        }
+    }
+    string
+}
+
+/// Formats a date time without colours, see `date_parts` for the format.
+fn strftime(dt: DT, format: &str) -> String {
+    date_parts(dt, format).into_iter().map(|(_, part)| part).collect()
+}
+
+/// Pieces of a date time formatted with a small part of strftime:
+/// `%Y`, `%m`, `%d`, `%H`, `%M` and `%%`. Minutes are not logged, so `%M` is always `00`.
+/// Each piece is paired with whether it holds year, month or day digits.
+fn date_parts((y, m, d, h): DT, format: &str) -> Vec<(bool, String)> {
+    let mut parts: Vec<(bool, String)> = Vec::new();
+    let mut chars = format.chars();
+    while let Some(ch) = chars.next(){
+        let (digits, part) = if ch != '%' {
+            (false, ch.to_string())
+        } else {
+            match chars.next(){
+                Some('Y') => (true, format!("{}", y)),
+                Some('m') => (true, format!("{:0>2}", m)),
+                Some('d') => (true, format!("{:0>2}", d)),
+                Some('H') => (false, format!("{:0>2}", h)),
+                Some('M') => (false, "00".to_string()),
+                Some('%') => (false, "%".to_string()),
+                Some(other) => (false, format!("%{}", other)),
+                None => (false, "%".to_string()),
+            }
+        };
+        match parts.last_mut(){
+            Some(last) if last.0 == digits => last.1.push_str(&part),
+            _ => parts.push((digits, part)),
+        }
+    }
+    parts
 }
 
@@ -0,0 +1,111 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fs,
+    path::{ Path, PathBuf },
+    time::{ SystemTime, UNIX_EPOCH },
+};
+
+use crate::{ Event, Events, Marker, summarize };
+
+/// Prints metrics in the Prometheus text exposition format, or writes them to `file`.
+/// The file is replaced atomically, so node_exporter's textfile collector never reads half of it.
+pub fn metrics(events: Events, file: Option<PathBuf>){
+    let text = exposition(&events);
+    let Some(path) = file else {
+        print!("{}", text);
+        return;
+    };
+    // the collector only reads *.prom files, so it skips the temporary file
+    let mut tmp = OsString::from(path.as_os_str());
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    if let Err(e) = fs::write(&tmp, &text).and_then(|_| fs::rename(&tmp, &path)) {
+        panic!("Error: could not write '{}': {}", path.display(), e);
+    }
+}
+
+fn exposition(events: &Events) -> String{
+    let summary = summarize(events);
+    let mut text = String::new();
+    family(&mut text, "paclog_installed_packages", "gauge",
+        "Packages installed according to the log.",
+        &[("", summary.packages as i64)]);
+    family(&mut text, "paclog_package_events_total", "counter",
+        "Package events in the log by kind.",
+        &[
+            ("kind=\"installed\"", summary.installs as i64),
+            ("kind=\"removed\"", summary.removes as i64),
+            ("kind=\"upgraded\"", summary.upgrades as i64),
+            ("kind=\"downgraded\"", summary.downgrades as i64),
+        ]);
+    family(&mut text, "paclog_updates_total", "counter",
+        "Commands that upgraded packages.",
+        &[("", summary.updates as i64)]);
+    let last_full_upgrade = events.iter().rev().find_map(|event| match event{
+        Event::Marker(_, time, Marker::FullUpgrade) => Some(*time),
+        _ => None,
+    });
+    if let Some(time) = last_full_upgrade {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+        family(&mut text, "paclog_last_full_upgrade_timestamp_seconds", "gauge",
+            "Unix time of the last full system upgrade.",
+            &[("", time)]);
+        family(&mut text, "paclog_seconds_since_last_full_upgrade", "gauge",
+            "Seconds since the last full system upgrade.",
+            &[("", now - time)]);
+    }
+    family(&mut text, "paclog_failed_transactions_total", "counter",
+        "Transactions that failed, were interrupted or never completed.",
+        &[("", failed_transactions(events) as i64)]);
+    family(&mut text, "paclog_pacnew_files", "gauge",
+        "Pacnew files from the log that still exist.",
+        &[("", outstanding_pacnews(events) as i64)]);
+    text
+}
+
+/// A metric with its help and type lines, `samples` are label sets with their values.
+fn family(text: &mut String, name: &str, kind: &str, help: &str, samples: &[(&str, i64)]){
+    text.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+    for (labels, value) in samples{
+        if labels.is_empty() {
+            text.push_str(&format!("{} {}\n", name, value));
+        } else {
+            text.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+        }
+    }
+}
+
+/// A transaction that starts while another one is still open never completed, pacman or the
+/// machine went down during it. The last transaction may still be running, so it is left out.
+fn failed_transactions(events: &Events) -> usize{
+    let mut failed = 0;
+    let mut open = false;
+    for event in events{
+        match event{
+            Event::Command(_, _) | Event::Marker(_, _, Marker::Started) => {
+                if open { failed += 1; }
+                open = matches!(event, Event::Marker(_, _, Marker::Started));
+            },
+            Event::Marker(_, _, Marker::Completed) => open = false,
+            Event::Marker(_, _, Marker::Failed) => {
+                failed += 1;
+                open = false;
+            },
+            _ => { },
+        }
+    }
+    failed
+}
+
+fn outstanding_pacnews(events: &Events) -> usize{
+    events.iter()
+        .filter_map(|event| match event{
+            Event::Marker(_, _, Marker::Pacnew(path)) => Some(path.as_str()),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|path| Path::new(path).exists())
+        .count()
+}
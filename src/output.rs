@@ -0,0 +1,314 @@
+use std::{
+    hash::Hash,
+    fmt::Display,
+};
+
+use clap::ValueEnum;
+use serde::{ Serialize, Serializer };
+
+use crate::{ Event, FreqMap, DT, strftime, config::date_format };
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Output{
+    Text,
+    Json, // see the README for the schema
+    Csv,
+    Tsv,
+}
+
+impl Output{
+    /// Whether the output is meant for other programs rather than people.
+    pub fn structured(self) -> bool{
+        self != Output::Text
+    }
+}
+
+/// A single event as it appears in structured output.
+#[derive(Serialize)]
+pub struct Record<'a>{
+    #[serde(skip)]
+    pub dt: DT,
+    pub date: String,
+    pub kind: &'static str,
+    pub package: Option<&'a str>,
+    pub old_version: Option<&'a str>,
+    pub new_version: Option<&'a str>,
+    pub command: Option<&'a str>,
+}
+
+impl<'a> Record<'a>{
+    /// Command events always carry their own command, for package events it is the command that
+    /// ran the transaction, if known.
+    pub fn new(event: &'a Event, command: Option<&'a str>) -> Self{
+        let (old_version, new_version) = event.versions();
+        let command = match event{
+            Event::Command(_, command) => Some(command.as_str()),
+            _ => command,
+        };
+        Self{
+            dt: event.dt(),
+            date: iso_dt(event.dt()),
+            kind: event.kind(),
+            package: event.package(),
+            old_version,
+            new_version,
+            command,
+        }
+    }
+}
+
+/// A line template like `{date:%Y-%m-%d} {kind} {package} {old}->{new}`, for printing events
+/// exactly the way a script wants them. `{{` and `}}` are literal braces.
+#[derive(Clone, Debug)]
+pub struct Template{
+    pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug)]
+enum Piece{
+    Literal(String),
+    Date(Option<String>),
+    Kind,
+    Package,
+    Old,
+    New,
+    Version,
+    Command,
+}
+
+pub const PLACEHOLDERS: &str = "date, date:FORMAT, kind, package, old, new, version, command";
+
+impl Template{
+    pub fn parse(template: &str) -> Result<Self, String>{
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(ch) = chars.next(){
+            match ch{
+                '{' if chars.peek() == Some(&'{') => { chars.next(); literal.push('{'); },
+                '}' if chars.peek() == Some(&'}') => { chars.next(); literal.push('}'); },
+                '{' => {
+                    let mut name = String::new();
+                    loop{
+                        match chars.next(){
+                            Some('}') => break,
+                            Some(ch) => name.push(ch),
+                            None => return Err(format!("unclosed placeholder '{{{}'", name)),
+                        }
+                    }
+                    let piece = match name.split_once(':'){
+                        Some(("date", format)) => Piece::Date(Some(format.to_string())),
+                        Some(_) => return Err(format!("unknown placeholder '{{{}}}'", name)),
+                        None => match name.as_str(){
+                            "date" => Piece::Date(None),
+                            "kind" => Piece::Kind,
+                            "package" => Piece::Package,
+                            "old" => Piece::Old,
+                            "new" => Piece::New,
+                            "version" => Piece::Version,
+                            "command" => Piece::Command,
+                            _ => return Err(format!("unknown placeholder '{{{}}}'", name)),
+                        },
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(piece);
+                },
+                '}' => return Err("unmatched '}', use '}}' for a literal brace".to_string()),
+                ch => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self{ pieces })
+    }
+
+    /// Fills in the template for an event, `command` is the command that ran the transaction.
+    /// Placeholders that don't apply to the event are left empty.
+    pub fn render(&self, event: &Event, command: Option<&str>) -> String{
+        let (old, new) = event.versions();
+        let mut string = String::new();
+        for piece in &self.pieces{
+            match piece{
+                Piece::Literal(literal) => string.push_str(literal),
+                Piece::Date(format) => string.push_str(
+                    &strftime(event.dt(), format.as_deref().unwrap_or(date_format()))
+                ),
+                Piece::Kind => string.push_str(event.kind()),
+                Piece::Package => string.push_str(event.package().unwrap_or("")),
+                Piece::Old => string.push_str(old.unwrap_or("")),
+                Piece::New => string.push_str(new.unwrap_or("")),
+                Piece::Version => string.push_str(event.version().unwrap_or("")),
+                Piece::Command => string.push_str(command.unwrap_or("")),
+            }
+        }
+        string
+    }
+}
+
+/// For clap, so a bad template is reported like any other bad argument.
+pub fn parse_template(template: &str) -> Result<Template, String>{
+    Template::parse(template).map_err(|e| format!("{}, placeholders are: {}", e, PLACEHOLDERS))
+}
+
+/// A frequency table, the structured version of what `print_map` prints.
+#[derive(Serialize)]
+pub struct Table<T>{
+    pub total: usize,
+    pub distinct: usize,
+    pub rows: Vec<Row<T>>,
+}
+
+#[derive(Serialize)]
+pub struct Row<T>{
+    pub value: T,
+    pub count: usize,
+    #[serde(serialize_with = "serialize_percent")]
+    pub percent: f32,
+}
+
+impl<T: PartialEq + Eq + PartialOrd + Hash> FreqMap<T>{
+    pub fn table(self, n: usize, sort_by_freq: bool) -> Table<T>{
+        let total = self.total();
+        let distinct = self.len();
+        let vec = if sort_by_freq {
+            self.sorted_by_freq()
+        } else {
+            self.sorted_by_key()
+        };
+        let rows = vec.into_iter().take(n).map(|(value, count)| Row{
+            value,
+            count,
+            percent: count as f32 / total as f32 * 100.0,
+        }).collect();
+        Table{ total, distinct, rows }
+    }
+}
+
+/// Something that can be printed as the rows of a csv or tsv table.
+pub trait Tabular{
+    const HEADER: &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+impl Tabular for Record<'_>{
+    const HEADER: &'static [&'static str] =
+        &["date", "kind", "package", "old_version", "new_version", "command"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.date.clone(),
+            self.kind.to_string(),
+            self.package.unwrap_or("").to_string(),
+            self.old_version.unwrap_or("").to_string(),
+            self.new_version.unwrap_or("").to_string(),
+            self.command.unwrap_or("").to_string(),
+        ]
+    }
+}
+
+impl<T: Display> Tabular for Row<T>{
+    const HEADER: &'static [&'static str] = &["value", "count", "percent"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![self.value.to_string(), self.count.to_string(), format!("{:.2}", self.percent)]
+    }
+}
+
+/// Prints the header and rows as csv or tsv, depending on the output.
+pub fn print_delimited<T: Tabular>(output: Output, rows: &[T]){
+    let line = |fields: Vec<String>| {
+        let fields = fields.iter().map(|f| escape_field(output, f)).collect::<Vec<_>>();
+        let separator = if output == Output::Tsv { "\t" } else { "," };
+        println!("{}", fields.join(separator));
+    };
+    line(T::HEADER.iter().map(|h| h.to_string()).collect());
+    for row in rows{
+        line(row.fields());
+    }
+}
+
+fn escape_field(output: Output, field: &str) -> String{
+    if output == Output::Tsv {
+        // tsv has no quoting, so tabs and newlines can't survive
+        field.replace(['\t', '\n'], " ")
+    } else if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Prints structured data in the format asked for, text output is left to the caller.
+pub fn print_structured<T: Serialize, R: Tabular>(output: Output, value: &T, rows: &[R]){
+    match output{
+        Output::Json => print_json(value),
+        Output::Csv | Output::Tsv => print_delimited(output, rows),
+        Output::Text => { },
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T){
+    println!("{}", serde_json::to_string_pretty(value).expect("Error: could not serialize output!"));
+}
+
+/// Date time in the form of `2023-06-30T02:00`.
+pub fn iso_dt((y, m, d, h): DT) -> String{
+    format!("{:0>4}-{:0>2}-{:0>2}T{:0>2}:00", y, m, d, h)
+}
+
+pub fn serialize_dt<S: Serializer>(dt: &DT, serializer: S) -> Result<S::Ok, S::Error>{
+    serializer.serialize_str(&iso_dt(*dt))
+}
+
+/// Percentages with two decimals, like in csv and text.
+fn serialize_percent<S: Serializer>(percent: &f32, serializer: S) -> Result<S::Ok, S::Error>{
+    serializer.serialize_f64((*percent as f64 * 100.0).round() / 100.0)
+}
+
+pub fn serialize_option_dt<S: Serializer>(dt: &Option<DT>, serializer: S)
+    -> Result<S::Ok, S::Error>
+{
+    match dt{
+        Some(dt) => serialize_dt(dt, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn template_renders_every_placeholder(){
+        let event = Event::Upgraded((2023, 6, 30, 2), "linux".into(), "(6.4.1 -> 6.4.2)".into());
+        let template = "{date:%Y-%m-%d} {kind} {package} {old}->{new} {command} {{}}";
+        let template = Template::parse(template).unwrap();
+        assert_eq!(
+            template.render(&event, Some("pacman -Syu")),
+            "2023-06-30 upgraded linux 6.4.1->6.4.2 pacman -Syu {}"
+        );
+        let installed = Event::Installed((2023, 6, 30, 2), "vim".into(), "(9.1-1)".into());
+        let template = Template::parse("{old}|{version}|{command}").unwrap();
+        assert_eq!(template.render(&installed, None), "|9.1-1|");
+    }
+
+    #[test]
+    fn template_rejects_bad_placeholders(){
+        for bad in ["{nope}", "{date", "}", "{kind:%Y}", "a {package"]{
+            assert!(Template::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn percentages_are_rounded_like_in_csv(){
+        let row = Row{ value: "linux", count: 2, percent: 2.0 / 3.0 * 100.0 };
+        assert_eq!(row.fields()[2], "66.67");
+        assert_eq!(
+            serde_json::to_string(&row).unwrap(),
+            r#"{"value":"linux","count":2,"percent":66.67}"#
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    env,
+    ffi::OsString,
+    io::{ self, IsTerminal, Write },
+    process::{ self, Command, Stdio },
+};
+
+use crate::colour::{ ColourMode, colours };
+
+const DEFAULT_PAGER: &str = "less -R";
+// set for the run whose output is paged, so it doesn't try to page again, to `always` or `never`
+const PAGED: &str = "PACLOG_PAGED";
+
+/// Whether output is for a person at a terminal, and not already going through a pager.
+pub fn wants_pager() -> bool{
+    env::var_os(PAGED).is_none() && io::stdout().is_terminal()
+}
+
+/// The colours decided by the run that pages this one, they win over any `--color`.
+pub fn paged_colour() -> Option<ColourMode>{
+    match env::var(PAGED).ok()?.as_str(){
+        "always" => Some(ColourMode::Always),
+        "never" => Some(ColourMode::Never),
+        _ => None,
+    }
+}
+
+/// Runs paclog again with the same arguments and collects its output. Output taller than the
+/// terminal goes through `$PAGER`, the rest is printed as is. Colours are decided here, where
+/// stdout is still the terminal, and passed on.
+pub fn run_paged(args: &[OsString]) -> !{
+    let exe = env::current_exe()
+        .unwrap_or_else(|e| panic!("Error: could not find the paclog executable: {}", e));
+    let colour = if colours().reset.is_empty() { "never" } else { "always" };
+    let output = Command::new(exe)
+        .args(&args[1..])
+        .env(PAGED, colour)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap_or_else(|e| panic!("Error: could not run paclog: {}", e));
+    let height = term_size::dimensions().map_or(0, |(_, h)| h);
+    let lines = output.stdout.iter().filter(|b| **b == b'\n').count();
+    if !output.status.success() || lines < height || !page(&output.stdout) {
+        let _ = io::stdout().write_all(&output.stdout);
+    }
+    process::exit(output.status.code().unwrap_or(1));
+}
+
+/// Shows text in the pager, returns false when there is no pager to show it in.
+fn page(text: &[u8]) -> bool{
+    let pager = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next() else { return false; };
+    let Ok(mut child) = Command::new(program).args(words).stdin(Stdio::piped()).spawn() else {
+        return false;
+    };
+    // quitting the pager early closes the pipe, that is fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text);
+    }
+    let _ = child.wait();
+    true
+}
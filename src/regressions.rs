@@ -0,0 +1,139 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, replay, hours, format_dt, format_hours,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, serialize_option_dt, iso_dt },
+    version::vercmp,
+};
+
+/// A downgrade with the upgrade it reverted and what happened to the package afterwards.
+#[derive(Serialize)]
+struct Regression<'a>{
+    package: &'a str,
+    #[serde(serialize_with = "serialize_dt")]
+    downgraded: DT,
+    // the version that was rolled back
+    bad_version: &'a str,
+    version: &'a str,
+    // the upgrade that brought the bad version, none when it is not in the log
+    #[serde(serialize_with = "serialize_option_dt")]
+    upgraded: Option<DT>,
+    upgrade_command: Option<&'a str>,
+    bad_hours: Option<i64>,
+    // fixed: upgraded past the bad version, held: still installed at or below it, removed
+    status: &'static str,
+    #[serde(serialize_with = "serialize_option_dt")]
+    since: Option<DT>,
+    current_version: Option<&'a str>,
+}
+
+impl Tabular for Regression<'_>{
+    const HEADER: &'static [&'static str] = &[
+        "package", "downgraded", "bad_version", "version", "upgraded", "upgrade_command",
+        "bad_hours", "status", "since", "current_version",
+    ];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            iso_dt(self.downgraded),
+            self.bad_version.to_string(),
+            self.version.to_string(),
+            self.upgraded.map(iso_dt).unwrap_or_default(),
+            self.upgrade_command.unwrap_or("").to_string(),
+            self.bad_hours.map(|h| h.to_string()).unwrap_or_default(),
+            self.status.to_string(),
+            self.since.map(iso_dt).unwrap_or_default(),
+            self.current_version.unwrap_or("").to_string(),
+        ]
+    }
+}
+
+/// For every downgrade: the upgrade it reverted, how long the bad version was installed, and
+/// whether the package got past the bad version again or is still held back.
+pub fn regressions(events: Events, now: i64, output: Output){
+    let mut command = None;
+    let commands = events.iter()
+        .map(|e| {
+            if let Event::Command(_, c) = e { command = Some(c.as_str()); }
+            command
+        })
+        .collect::<Vec<_>>();
+    let installed = replay(&events);
+    let mut regressions = Vec::new();
+    for (i, event) in events.iter().enumerate(){
+        let Event::Downgraded(dt, package, _) = event else { continue; };
+        let (Some(bad_version), Some(version)) = event.versions() else { continue; };
+        let upgrade = events[..i].iter().enumerate().rev()
+            .filter(|(_, e)| e.package() == Some(package))
+            .find(|(_, e)| matches!(e, Event::Upgraded(_, _, _) | Event::Installed(_, _, _)))
+            .filter(|(_, e)| e.versions().1 == Some(bad_version));
+        let upgraded = upgrade.map(|(_, e)| e.dt());
+        let later = events[i + 1..].iter()
+            .filter(|e| e.package() == Some(package))
+            .find(|e| match e{
+                Event::Removed(_, _, _) => true,
+                _ => e.versions().1.is_some_and(|v| vercmp(v, bad_version) == Ordering::Greater),
+            });
+        let (status, since, current_version) = match later{
+            Some(e @ Event::Removed(_, _, _)) => ("removed", Some(e.dt()), None),
+            Some(e) => ("fixed", Some(e.dt()), e.versions().1),
+            None => ("held", None, installed.get(package.as_str()).copied()),
+        };
+        regressions.push(Regression{
+            package,
+            downgraded: *dt,
+            bad_version,
+            version,
+            upgraded,
+            upgrade_command: upgrade.and_then(|(j, _)| commands[j]),
+            bad_hours: upgraded.map(|u| hours(*dt) - hours(u)),
+            status,
+            since,
+            current_version,
+        });
+    }
+    if output.structured() {
+        print_structured(output, &regressions, &regressions);
+        return;
+    }
+
+    let c = colours();
+    for r in &regressions{
+        println!(
+            "{}{}{} {}{} -> {}{} {}downgraded{} {}",
+            c.package, r.package, c.reset, c.version, r.bad_version, r.version, c.reset,
+            c.downgrade, c.reset, format_dt(r.downgraded)
+        );
+        match (r.upgraded, r.bad_hours){
+            (Some(upgraded), Some(bad_hours)) => println!(
+                "\treverted the upgrade of {} with: {}{}{}, {} was installed for {}",
+                format_dt(upgraded), c.command, r.upgrade_command.unwrap_or(""), c.reset,
+                r.bad_version, format_hours(bad_hours)
+            ),
+            _ => println!("\tthe upgrade to {} is not in the log", r.bad_version),
+        }
+        match (r.status, r.since){
+            ("fixed", Some(since)) => println!(
+                "\t{}upgraded{} past it {} to {}{}{}, {} after the downgrade",
+                c.upgrade, c.reset, format_dt(since), c.version,
+                r.current_version.unwrap_or(""), c.reset,
+                format_hours(hours(since) - hours(r.downgraded))
+            ),
+            ("removed", Some(since)) => println!(
+                "\t{}removed{} {} without getting past it", c.remove, c.reset, format_dt(since)
+            ),
+            _ => println!(
+                "\t{}still held back{} at {}, {} after the downgrade",
+                c.bold, c.reset, r.current_version.unwrap_or(r.version),
+                format_hours(now - hours(r.downgraded))
+            ),
+        }
+    }
+    if regressions.is_empty() {
+        println!("No downgrades found.");
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+use crate::{
+    Events, Transaction, TimeTables,
+    summarize, frequencies, time_stats, transactions, strftime,
+    output::{ Table, Row },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat{
+    Md,
+    Html,
+}
+
+/// The parts a report is made of, rendered to markdown or html at the end.
+enum Block{
+    Title(String),
+    Heading(String),
+    Paragraph(String),
+    Table(Vec<String>, Vec<Vec<String>>), // header, rows
+    Chart(String), // svg
+}
+
+pub const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Prints one self-contained document with the summary, top lists, time tables and the most
+/// recent transactions. `n` is the length of the top lists, `recent` the amount of transactions.
+pub fn report(events: Events, format: ReportFormat, n: usize, recent: usize){
+    let mut blocks = vec![Block::Title("Pacman report".to_string())];
+    let summary = summarize(&events);
+    if let (Some(first), Some(last)) = (events.first(), events.last()) {
+        blocks.push(Block::Paragraph(format!(
+            "Generated by paclog {} from {} events, logged between {} and {}.",
+            env!("CARGO_PKG_VERSION"), summary.events,
+            strftime(first.dt(), "%Y-%m-%d"), strftime(last.dt(), "%Y-%m-%d"),
+        )));
+    }
+
+    blocks.push(Block::Heading("Summary".to_string()));
+    let metrics = [
+        ("Packages installed", summary.packages), ("Events", summary.events),
+        ("Updates", summary.updates), ("Installs", summary.installs),
+        ("Removes", summary.removes), ("Upgrades", summary.upgrades),
+        ("Downgrades", summary.downgrades),
+    ];
+    blocks.push(Block::Table(
+        vec!["Metric".to_string(), "Value".to_string()],
+        metrics.iter().map(|(k, v)| vec![k.to_string(), v.to_string()]).collect(),
+    ));
+
+    blocks.push(Block::Heading("Commands per year".to_string()));
+    push_table(&mut blocks, &summary.commands_per_year, "Year", true, |y| y.to_string());
+
+    let tops = [
+        ("Most upgraded packages", "upgraded", "Package"),
+        ("Most installed packages", "installed", "Package"),
+        ("Most removed packages", "removed", "Package"),
+        ("Most downgraded packages", "downgraded", "Package"),
+        ("Most run commands", "command", "Command"),
+    ];
+    for (heading, kind, key) in tops{
+        let table = frequencies(&events, kind).table(n, true);
+        if table.rows.is_empty() { continue; }
+        blocks.push(Block::Heading(heading.to_string()));
+        push_table(&mut blocks, &table, key, kind == "upgraded", |k| k.to_string());
+    }
+
+    let stats = time_stats(&events, false, true, false, true);
+    let month = |m: &u16| MONTHS.get(*m as usize - 1).unwrap_or(&"?").to_string();
+    push_time(&mut blocks, "Activity per month", "Month", &stats.per_month, month);
+    push_time(&mut blocks, "Activity per hour", "Hour", &stats.per_hour, |h| format!("{:0>2}:00", h));
+
+    let history = transactions(&events).into_iter()
+        .filter(|t| t.shown(false))
+        .collect::<Vec<_>>();
+    let history = &history[history.len().saturating_sub(recent)..];
+    if !history.is_empty() {
+        blocks.push(Block::Heading("Recent history".to_string()));
+        blocks.push(Block::Table(
+            ["Date", "Action", "Packages", "Command"].map(String::from).to_vec(),
+            history.iter().rev().map(|t| vec![
+                strftime(t.dt, "%Y-%m-%d %H:%M"),
+                action(t).to_string(),
+                packages(t),
+                t.command.clone(),
+            ]).collect(),
+        ));
+    }
+
+    match format{
+        ReportFormat::Md => print!("{}", markdown(&blocks)),
+        ReportFormat::Html => print!("{}", html(&blocks)),
+    }
+}
+
+fn push_table<T: Display>(
+    blocks: &mut Vec<Block>, table: &Table<T>, key: &str, chart: bool, label: impl Fn(&T) -> String
+){
+    if chart {
+        let bars = table.rows.iter().map(|r| (label(&r.value), r.count)).collect::<Vec<_>>();
+        blocks.push(Block::Chart(bar_chart(&bars)));
+    }
+    blocks.push(Block::Table(
+        vec![key.to_string(), "Count".to_string(), "Percent".to_string()],
+        table.rows.iter().map(|Row{ value, count, percent }| vec![
+            label(value), count.to_string(), format!("{:.2}%", percent),
+        ]).collect(),
+    ));
+}
+
+/// A chart of the commands and one table with every category, one row per period.
+fn push_time(
+    blocks: &mut Vec<Block>, heading: &str, key: &str, tables: &Option<TimeTables>,
+    label: impl Fn(&u16) -> String
+){
+    let Some(t) = tables else { return; };
+    if t.commands.rows.is_empty() { return; }
+    blocks.push(Block::Heading(heading.to_string()));
+    let bars = t.commands.rows.iter().map(|r| (label(&r.value), r.count)).collect::<Vec<_>>();
+    blocks.push(Block::Chart(bar_chart(&bars)));
+    let columns = [&t.commands, &t.installs, &t.removes, &t.upgrades, &t.downgrades];
+    let mut keys = columns.iter().flat_map(|t| t.rows.iter().map(|r| r.value)).collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys.dedup();
+    let count = |table: &Table<u16>, key: u16| {
+        table.rows.iter().find(|r| r.value == key).map_or(0, |r| r.count).to_string()
+    };
+    blocks.push(Block::Table(
+        [key, "Commands", "Installs", "Removes", "Upgrades", "Downgrades"]
+            .map(String::from).to_vec(),
+        keys.into_iter().map(|k| {
+            let mut row = vec![label(&k)];
+            row.extend(columns.iter().map(|t| count(t, k)));
+            row
+        }).collect(),
+    ));
+}
+
+pub fn action(t: &Transaction) -> &'static str{
+    let has = (
+        !t.install.is_empty(), !t.remove.is_empty(),
+        !t.upgrade.is_empty(), !t.downgrade.is_empty()
+    );
+    match has{
+        (true, false, false, false) => "install",
+        (false, true, false, false) => "remove",
+        (false, false, true, false) => "upgrade",
+        (false, false, false, true) => "downgrade",
+        _ => "complex",
+    }
+}
+
+/// The named packages, with the amount of other packages that came along.
+pub fn packages(t: &Transaction) -> String{
+    let mut string = t.named.join(", ");
+    if !t.unnamed.is_empty() {
+        if !string.is_empty() { string.push(' '); }
+        string.push_str(&format!("(+{} more)", t.unnamed.len()));
+    }
+    string
+}
+
+/// A horizontal bar chart, scaled to the largest value.
+fn bar_chart(bars: &[(String, usize)]) -> String{
+    const LABEL: usize = 160;
+    const BAR: usize = 400;
+    const ROW: usize = 20;
+    let max = bars.iter().map(|(_, v)| *v).max().unwrap_or(0).max(1);
+    let height = bars.len() * ROW;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+        font-family=\"sans-serif\" font-size=\"12\">\n",
+        LABEL + BAR + 60, height
+    );
+    for (i, (label, value)) in bars.iter().enumerate(){
+        let y = i * ROW;
+        let width = (value * BAR / max).max(1);
+        let label = if label.chars().count() > 24 {
+            format!("{}…", label.chars().take(23).collect::<String>())
+        } else {
+            label.clone()
+        };
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+            <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#4878a8\"/>\
+            <text x=\"{}\" y=\"{}\">{}</text>\n",
+            LABEL - 6, y + 14, escape_html(&label),
+            LABEL, y + 3, width, ROW - 6,
+            LABEL + width + 4, y + 14, value,
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn markdown(blocks: &[Block]) -> String{
+    let cell = |s: &str| s.replace('|', "\\|");
+    let mut md = String::new();
+    for block in blocks{
+        match block{
+            Block::Title(title) => md.push_str(&format!("# {}\n\n", title)),
+            Block::Heading(heading) => md.push_str(&format!("## {}\n\n", heading)),
+            Block::Paragraph(text) => md.push_str(&format!("{}\n\n", text)),
+            Block::Table(header, rows) => {
+                let line = |cells: &[String]| {
+                    format!("| {} |\n", cells.iter().map(|c| cell(c)).collect::<Vec<_>>().join(" | "))
+                };
+                md.push_str(&line(header));
+                md.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+                for row in rows{
+                    md.push_str(&line(row));
+                }
+                md.push('\n');
+            },
+            Block::Chart(svg) => md.push_str(&format!("{}\n\n", svg)),
+        }
+    }
+    md
+}
+
+fn html(blocks: &[Block]) -> String{
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Pacman report</title>\n\
+        <style>\n\
+        body { font-family: sans-serif; max-width: 60em; margin: 2em auto; color: #222; }\n\
+        table { border-collapse: collapse; margin-bottom: 1em; }\n\
+        th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }\n\
+        th { background: #eee; }\n\
+        svg { display: block; margin-bottom: 1em; }\n\
+        </style>\n</head>\n<body>\n"
+    );
+    for block in blocks{
+        match block{
+            Block::Title(title) => html.push_str(&format!("<h1>{}</h1>\n", escape_html(title))),
+            Block::Heading(heading) => {
+                html.push_str(&format!("<h2>{}</h2>\n", escape_html(heading)));
+            },
+            Block::Paragraph(text) => html.push_str(&format!("<p>{}</p>\n", escape_html(text))),
+            Block::Table(header, rows) => {
+                html.push_str("<table>\n<tr>");
+                for cell in header{
+                    html.push_str(&format!("<th>{}</th>", escape_html(cell)));
+                }
+                html.push_str("</tr>\n");
+                for row in rows{
+                    html.push_str("<tr>");
+                    for cell in row{
+                        html.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                    }
+                    html.push_str("</tr>\n");
+                }
+                html.push_str("</table>\n");
+            },
+            Block::Chart(svg) => html.push_str(&format!("{}\n", svg)),
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn escape_html(s: &str) -> String{
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,150 @@
+use std::{
+    fs,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, replay, format_dt, fail,
+    colour::colours,
+    output::{ Output, Tabular, print_structured },
+    state::events_before,
+};
+
+/// A package to bring back to the version it had, with the cached package file if there is one.
+#[derive(Serialize)]
+struct Restore<'a>{
+    package: &'a str,
+    // none when it was removed since
+    current_version: Option<&'a str>,
+    version: &'a str,
+    file: Option<String>,
+}
+
+impl Tabular for Restore<'_>{
+    const HEADER: &'static [&'static str] = &["package", "current_version", "version", "file"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            self.current_version.unwrap_or("").to_string(),
+            self.version.to_string(),
+            self.file.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// The events before the last `undo` transactions that changed packages.
+pub fn events_before_transactions(events: &Events, undo: usize) -> &[Event]{
+    let mut starts = Vec::new();
+    let mut command = None;
+    for (i, event) in events.iter().enumerate(){
+        match event{
+            Event::Command(_, _) => command = Some(i),
+            Event::Marker(_, _, _) => { },
+            _ => if let Some(start) = command.take() { starts.push(start); },
+        }
+    }
+    match starts.len().checked_sub(undo){
+        Some(i) if undo > 0 => &events[..starts[i]],
+        _ => fail(format!("there are only {} transactions to undo!", starts.len())),
+    }
+}
+
+/// Prints how to go back to the versions of a point in time: which packages to downgrade, upgrade
+/// or reinstall from the package cache, in one `pacman -U` command. Versions that are not in the
+/// cache are marked, packages installed since are listed. Nothing is run.
+pub fn rollback(events: Events, at: Option<DT>, undo: Option<usize>, cache: &str, output: Output){
+    let (target, description) = match (at, undo){
+        (_, Some(undo)) => (
+            events_before_transactions(&events, undo),
+            format!("before the last {} transactions", undo),
+        ),
+        (Some(at), None) => (events_before(&events, at), format!("to {}", format_dt(at))),
+        (None, None) => fail("give a date or --undo!"),
+    };
+    plan(&events, target, &description, cache, output);
+}
+
+/// Prints the packages to restore to get from the end of `events` to the end of `target`,
+/// which is a prefix of them.
+pub fn plan(events: &Events, target: &[Event], description: &str, cache: &str, output: Output){
+    let (wanted, current) = (replay(target), replay(events));
+    let files = match fs::read_dir(cache){
+        Ok(dir) => dir.filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.contains(".pkg.tar.") && !name.ends_with(".sig"))
+            .collect::<Vec<_>>(),
+        // still a plan, only without files
+        Err(e) => {
+            eprintln!(
+                "Could not read the package cache '{}': {}, every version is missing.", cache, e
+            );
+            Vec::new()
+        },
+    };
+    let restores = wanted.iter()
+        .filter(|(package, version)| current.get(*package) != Some(version))
+        .map(|(package, version)| Restore{
+            package,
+            current_version: current.get(package).copied(),
+            version,
+            file: cached(&files, package, version)
+                .map(|f| Path::new(cache).join(f).to_string_lossy().to_string()),
+        })
+        .collect::<Vec<_>>();
+    let added = current.keys().filter(|p| !wanted.contains_key(*p)).collect::<Vec<_>>();
+    if output.structured() {
+        print_structured(output, &restores, &restores);
+        return;
+    }
+
+    let c = colours();
+    println!(
+        "Rolling back {}, {}{}{} packages to restore:",
+        description, c.total, restores.len(), c.reset
+    );
+    let versions = restores.iter()
+        .map(|r| format!("{} -> {}", r.current_version.unwrap_or("removed"), r.version))
+        .collect::<Vec<_>>();
+    let width = restores.iter().map(|r| r.package.chars().count()).max().unwrap_or(0);
+    let versions_width = versions.iter().map(|v| v.chars().count()).max().unwrap_or(0);
+    for (r, versions) in restores.iter().zip(&versions){
+        let file = match &r.file{
+            Some(file) => file.clone(),
+            None => format!("{}{}missing from the cache{}", c.bold, c.remove, c.reset),
+        };
+        println!(
+            "\t{}{:<width$}{} {}{:<versions_width$}{}  {}",
+            c.package, r.package, c.reset, c.version, versions, c.reset, file
+        );
+    }
+    if !added.is_empty() {
+        println!(
+            "Installed since, not part of the plan: {}",
+            added.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    let files = restores.iter().filter_map(|r| r.file.as_deref()).collect::<Vec<_>>();
+    let missing = restores.len() - files.len();
+    if !files.is_empty() {
+        println!("\n{}pacman -U {}{}", c.command, files.join(" "), c.reset);
+    }
+    if missing > 0 {
+        println!(
+            "\nMissing from '{}': {}{}{}, the command leaves them out.",
+            cache, c.remove, missing, c.reset
+        );
+    }
+}
+
+/// The file name of a cached package, they look like `name-version-arch.pkg.tar.zst`.
+fn cached<'a>(files: &'a [String], package: &str, version: &str) -> Option<&'a str>{
+    let prefix = format!("{}-{}-", package, version);
+    files.iter()
+        .map(String::as_str)
+        .find(|name| name.strip_prefix(&prefix).is_some_and(|rest| {
+            rest.split_once(".pkg.tar.").is_some_and(|(arch, _)| !arch.contains('-'))
+        }))
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, replay, intentional_packages, hours, format_dt, format_hours,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+};
+
+#[derive(Serialize)]
+struct Stale<'a>{
+    package: &'a str,
+    version: &'a str,
+    // installed or upgraded
+    change: &'static str,
+    #[serde(serialize_with = "serialize_dt")]
+    changed: DT,
+    age_hours: i64,
+    // named in an install command
+    intentional: bool,
+}
+
+impl Tabular for Stale<'_>{
+    const HEADER: &'static [&'static str] =
+        &["package", "version", "change", "changed", "age_hours", "intentional"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.to_string(),
+            self.version.to_string(),
+            self.change.to_string(),
+            iso_dt(self.changed),
+            self.age_hours.to_string(),
+            self.intentional.to_string(),
+        ]
+    }
+}
+
+/// Installed packages that were not installed or upgraded for at least `days`, oldest first.
+/// Packages that were installed on purpose are bold, those are the ones to look after.
+pub fn stale(events: Events, now: i64, days: u32, output: Output){
+    let mut last: BTreeMap<&str, (&'static str, DT)> = BTreeMap::new();
+    for event in &events{
+        match event{
+            Event::Installed(dt, package, _) => { last.insert(package, ("installed", *dt)); },
+            Event::Upgraded(dt, package, _) => { last.insert(package, ("upgraded", *dt)); },
+            _ => { },
+        }
+    }
+    let installed = replay(&events);
+    let (named, _) = intentional_packages(&events);
+    let mut packages = installed.iter()
+        .filter_map(|(package, version)| {
+            let (change, changed) = *last.get(package)?;
+            Some(Stale{
+                package,
+                version,
+                change,
+                changed,
+                age_hours: now - hours(changed),
+                intentional: named.contains(*package),
+            })
+        })
+        .filter(|s| s.age_hours >= days as i64 * 24)
+        .collect::<Vec<_>>();
+    packages.sort_by_key(|s| (std::cmp::Reverse(s.age_hours), s.package));
+    if output.structured() {
+        print_structured(output, &packages, &packages);
+        return;
+    }
+
+    let c = colours();
+    println!(
+        "Unchanged for {} days or more: {}{}{} of {} installed, {}{}{} of them on purpose ({}*{})",
+        days, c.total, packages.len(), c.reset, installed.len(),
+        c.install, packages.iter().filter(|s| s.intentional).count(), c.reset, c.install, c.reset
+    );
+    let width = packages.iter().map(|s| s.package.chars().count()).max().unwrap_or(0);
+    let version_width = packages.iter().map(|s| s.version.chars().count()).max().unwrap_or(0);
+    for s in &packages{
+        // a marker that stays when colours are off, and the install colour on top of the package's
+        let (marker, style) = if s.intentional { ("*", c.install.as_str()) } else { (" ", "") };
+        println!(
+            "\t{}{}{} {}{}{:<width$}{} {}{:<version_width$}{} {} {:<9} {} ago",
+            c.install, marker, c.reset, c.package, style, s.package, c.reset,
+            c.version, s.version, c.reset,
+            format_dt(s.changed), s.change, format_hours(s.age_hours)
+        );
+    }
+}
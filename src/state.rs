@@ -0,0 +1,108 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, replay, format_dt, days_from_civil,
+    colour::colours,
+    output::{ Output, Tabular, print_structured },
+};
+
+/// For clap: a point in time like `2024`, `2024-03`, `2024-03-01` or `2024-03-01T14`, minutes
+/// may follow but are not logged. It stands for the start of the period, so `2024-03-01` is
+/// everything up to that day.
+pub fn parse_date(s: &str) -> Result<DT, String>{
+    let error = || format!("'{}' is not a date like 2024, 2024-03, 2024-03-01 or 2024-03-01T14", s);
+    let (date, hour) = match s.split_once(['T', ' ']){
+        Some((date, time)) => (date, Some(time.split(':').next().unwrap_or(time))),
+        None => (s, None),
+    };
+    let mut parts = date.split('-');
+    let year = parts.next().and_then(|y| y.parse::<u16>().ok()).ok_or_else(error)?;
+    let mut number = |max: u8, default: u8| match parts.next(){
+        Some(part) => part.parse::<u8>().ok().filter(|n| (1..=max).contains(n)).ok_or_else(error),
+        None => Ok(default),
+    };
+    let month = number(12, 1)?;
+    let (y, m) = (year as i64, month as i64);
+    let month_days = days_from_civil(y + m / 12, m % 12 + 1, 1) - days_from_civil(y, m, 1);
+    let day = number(month_days as u8, 1)?;
+    if parts.next().is_some() { return Err(error()); }
+    let hour = match hour{
+        Some(h) => h.parse::<u8>().ok().filter(|h| *h < 24).ok_or_else(error)?,
+        None => 0,
+    };
+    Ok((year, month, day, hour))
+}
+
+/// The events before a point in time, logs are in order so this is a prefix.
+pub fn events_before(events: &Events, at: DT) -> &[Event]{
+    let end = events.iter().position(|e| e.dt() >= at).unwrap_or(events.len());
+    &events[..end]
+}
+
+#[derive(Serialize)]
+struct Installed<'a>{
+    package: &'a str,
+    version: &'a str,
+}
+
+impl Tabular for Installed<'_>{
+    const HEADER: &'static [&'static str] = &["package", "version"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![self.package.to_string(), self.version.to_string()]
+    }
+}
+
+/// Every package that was installed at a point in time with its exact version, by replaying the
+/// log up to there. Without a point in time the whole log is replayed.
+pub fn state(events: Events, at: Option<DT>, output: Output){
+    let replayed = match at{
+        Some(at) => events_before(&events, at),
+        None => &events,
+    };
+    let packages = replay(replayed).into_iter()
+        .map(|(package, version)| Installed{ package, version })
+        .collect::<Vec<_>>();
+    if output.structured() {
+        print_structured(output, &packages, &packages);
+        return;
+    }
+
+    let c = colours();
+    let width = packages.iter().map(|p| p.package.chars().count()).max().unwrap_or(0);
+    for Installed{ package, version } in &packages{
+        println!("{}{:<width$}{} {}{}{}", c.package, package, c.reset, c.version, version, c.reset);
+    }
+    match at{
+        Some(at) => println!(
+            "{}{}{} packages installed before {}", c.total, packages.len(), c.reset, format_dt(at)
+        ),
+        None => println!("{}{}{} packages installed", c.total, packages.len(), c.reset),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn dates_stand_for_the_start_of_their_period(){
+        assert_eq!(parse_date("2024"), Ok((2024, 1, 1, 0)));
+        assert_eq!(parse_date("2024-03"), Ok((2024, 3, 1, 0)));
+        assert_eq!(parse_date("2024-03-09"), Ok((2024, 3, 9, 0)));
+        assert_eq!(parse_date("2024-03-09T14"), Ok((2024, 3, 9, 14)));
+        assert_eq!(parse_date("2024-03-09 14:30"), Ok((2024, 3, 9, 14)));
+        // the last days of months
+        assert_eq!(parse_date("2024-02-29"), Ok((2024, 2, 29, 0)));
+        assert_eq!(parse_date("2023-12-31"), Ok((2023, 12, 31, 0)));
+    }
+
+    #[test]
+    fn bad_dates_are_rejected(){
+        for bad in ["", "yesterday", "2024-13", "2024-00", "2024-03-32", "2023-02-29", "2023-02-30",
+            "2024-04-31", "2024-03-09T24", "2024-03-09-01", "2024/03/09"]
+        {
+            assert!(parse_date(bad).is_err(), "{}", bad);
+        }
+    }
+}
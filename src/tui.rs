@@ -0,0 +1,411 @@
+use std::{
+    collections::HashSet,
+    io::{ self, IsTerminal, Stdout, Write },
+};
+
+use crossterm::{
+    cursor,
+    event::{ self, Event as TermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers },
+    queue,
+    style::{ Attribute, Print, SetAttribute },
+    terminal::{ self, ClearType },
+};
+
+use crate::{
+    Event, Events, Transaction, transactions, strftime,
+    colour::colours,
+    config::date_format,
+    report::{ action, packages },
+};
+
+/// A transaction with its package events, for expanding it in place.
+struct Item<'a>{
+    transaction: Transaction,
+    events: Vec<&'a Event>,
+}
+
+/// What a line of the list shows: a transaction, or one of its events when it is expanded.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line{
+    Transaction(usize),
+    Event(usize, usize),
+}
+
+enum Prompt{
+    Search,
+    Date,
+}
+
+struct State<'a>{
+    items: Vec<Item<'a>>,
+    expanded: HashSet<usize>,
+    lines: Vec<Line>,
+    cursor: usize,
+    top: usize,
+    kind: Option<&'static str>,
+    search: String,
+    date: String,
+    prompt: Option<Prompt>,
+    input: String,
+}
+
+const HELP: &str = "j/k move  enter expand  / search  D date  i/r/u/d kind  a all  g/G ends  q quit";
+
+/// A full screen view of the transactions, newest first. A transaction expands into its package
+/// events and the side panel shows the history of the selected package.
+pub fn tui(events: Events){
+    if !io::stdout().is_terminal() {
+        panic!("Error: tui needs a terminal!");
+    }
+    let mut groups: Vec<Vec<&Event>> = Vec::new();
+    for event in &events{
+        match event{
+            Event::Command(_, _) => groups.push(Vec::new()),
+            Event::Marker(_, _, _) => { },
+            _ => if let Some(group) = groups.last_mut() { group.push(event); },
+        }
+    }
+    let items = transactions(&events).into_iter()
+        .zip(groups)
+        .map(|(transaction, events)| Item{ transaction, events })
+        .rev()
+        .collect();
+    let mut state = State{
+        items,
+        expanded: HashSet::new(),
+        lines: Vec::new(),
+        cursor: 0,
+        top: 0,
+        kind: None,
+        search: String::new(),
+        date: String::new(),
+        prompt: None,
+        input: String::new(),
+    };
+    state.relist();
+
+    let mut out = io::stdout();
+    let _guard = Screen::enter(&mut out);
+    loop{
+        if let Err(e) = draw(&mut out, &mut state, &events) {
+            panic!("Error: could not draw: {}", e);
+        }
+        let Ok(event) = event::read() else { break; };
+        let TermEvent::Key(key) = event else { continue; };
+        if key.kind != KeyEventKind::Press { continue; }
+        if !state.handle(key) { break; }
+    }
+}
+
+/// Raw mode and the alternate screen, restored on drop so a panic leaves a usable terminal.
+struct Screen;
+
+impl Screen{
+    fn enter(out: &mut Stdout) -> Self{
+        let result = terminal::enable_raw_mode().and_then(|_| {
+            queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+            out.flush()
+        });
+        if let Err(e) = result {
+            panic!("Error: could not set up the terminal: {}", e);
+        }
+        Self
+    }
+}
+
+impl Drop for Screen{
+    fn drop(&mut self){
+        let mut out = io::stdout();
+        let _ = queue!(out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl State<'_>{
+    fn shown(&self, item: &Item) -> bool{
+        let t = &item.transaction;
+        let kind = match self.kind{
+            Some("installed") => !t.install.is_empty(),
+            Some("removed") => !t.remove.is_empty(),
+            Some("upgraded") => !t.upgrade.is_empty(),
+            Some("downgraded") => !t.downgrade.is_empty(),
+            _ => true,
+        };
+        let search = self.search.is_empty() ||
+            t.command.contains(&self.search) ||
+            item.events.iter().any(|e| e.package().is_some_and(|p| p.contains(&self.search)));
+        let date = strftime(t.dt, "%Y-%m-%d").starts_with(&self.date);
+        kind && search && date
+    }
+
+    /// Rebuilds the lines after filtering or expanding, keeping the cursor on the same line.
+    fn relist(&mut self){
+        let current = self.lines.get(self.cursor).copied();
+        self.lines.clear();
+        for (i, item) in self.items.iter().enumerate(){
+            if !self.shown(item) { continue; }
+            self.lines.push(Line::Transaction(i));
+            if self.expanded.contains(&i) {
+                self.lines.extend((0..item.events.len()).map(|e| Line::Event(i, e)));
+            }
+        }
+        self.cursor = current
+            .and_then(|line| self.lines.iter().position(|l| *l == line))
+            .unwrap_or(0);
+    }
+
+    fn selected_item(&self) -> Option<usize>{
+        match self.lines.get(self.cursor)?{
+            Line::Transaction(i) | Line::Event(i, _) => Some(*i),
+        }
+    }
+
+    /// Returns false to quit.
+    fn handle(&mut self, key: KeyEvent) -> bool{
+        if let Some(prompt) = &self.prompt {
+            match key.code{
+                KeyCode::Enter => {
+                    match prompt{
+                        Prompt::Search => self.search = std::mem::take(&mut self.input),
+                        Prompt::Date => self.date = std::mem::take(&mut self.input),
+                    }
+                    self.prompt = None;
+                    self.relist();
+                },
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.prompt = None;
+                },
+                KeyCode::Backspace => { self.input.pop(); },
+                KeyCode::Char(ch) => self.input.push(ch),
+                _ => { },
+            }
+            return true;
+        }
+        let page = terminal::size().map_or(20, |(_, h)| h as usize).saturating_sub(3).max(1);
+        let last = self.lines.len().saturating_sub(1);
+        match key.code{
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('j') | KeyCode::Down => self.cursor = (self.cursor + 1).min(last),
+            KeyCode::Char('k') | KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::PageDown => self.cursor = (self.cursor + page).min(last),
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(page),
+            KeyCode::Char('g') | KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('G') | KeyCode::End => self.cursor = last,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(i) = self.selected_item() {
+                    if !self.expanded.remove(&i) {
+                        self.expanded.insert(i);
+                    }
+                    // collapsing from an event moves the cursor up to its transaction
+                    self.cursor = self.lines.iter()
+                        .position(|l| *l == Line::Transaction(i))
+                        .unwrap_or(0);
+                    self.relist();
+                }
+            },
+            KeyCode::Char('/') => {
+                self.input = self.search.clone();
+                self.prompt = Some(Prompt::Search);
+            },
+            KeyCode::Char('D') => {
+                self.input = self.date.clone();
+                self.prompt = Some(Prompt::Date);
+            },
+            KeyCode::Char(ch @ ('i' | 'r' | 'u' | 'd')) => {
+                let kind = match ch{
+                    'i' => "installed",
+                    'r' => "removed",
+                    'u' => "upgraded",
+                    _ => "downgraded",
+                };
+                self.kind = if self.kind == Some(kind) { None } else { Some(kind) };
+                self.relist();
+            },
+            KeyCode::Char('a') => {
+                self.kind = None;
+                self.search.clear();
+                self.date.clear();
+                self.relist();
+            },
+            _ => { },
+        }
+        true
+    }
+}
+
+/// A piece of a line with its escape codes.
+type Span = (String, String);
+
+fn span(style: &str, text: impl Into<String>) -> Span{
+    (style.to_string(), text.into())
+}
+
+fn draw(out: &mut Stdout, state: &mut State, events: &Events) -> io::Result<()>{
+    let c = colours();
+    let (w, h) = terminal::size()?;
+    let (w, h) = (w as usize, h as usize);
+    let list_width = if w >= 60 { w * 3 / 5 } else { w };
+    let side_width = w.saturating_sub(list_width + 1);
+    let list_height = h.saturating_sub(2);
+    if state.cursor < state.top {
+        state.top = state.cursor;
+    } else if state.cursor >= state.top + list_height {
+        state.top = state.cursor + 1 - list_height;
+    }
+    queue!(out, terminal::Clear(ClearType::All))?;
+
+    let mut filters = Vec::new();
+    if let Some(kind) = state.kind { filters.push(kind.to_string()); }
+    if !state.search.is_empty() { filters.push(format!("/{}", state.search)); }
+    if !state.date.is_empty() { filters.push(format!("date {}", state.date)); }
+    let shown = state.lines.iter().filter(|l| matches!(l, Line::Transaction(_))).count();
+    let title = vec![
+        span(&c.bold, "paclog "),
+        span(&c.count, format!("{}/{} transactions ", shown, state.items.len())),
+        span(&c.key, filters.join("  ")),
+    ];
+    draw_spans(out, 0, 0, w, &title, false)?;
+
+    for row in 0..list_height{
+        let Some(line) = state.lines.get(state.top + row) else { break; };
+        let spans = match *line{
+            Line::Transaction(i) => {
+                let t = &state.items[i].transaction;
+                let marker = if state.expanded.contains(&i) { "▾ " } else { "▸ " };
+                vec![
+                    span("", marker),
+                    span(&c.date, strftime(t.dt, date_format())),
+                    span(kind_style(action(t)), format!(" {:<9} ", action(t))),
+                    span(&c.package, packages(t)),
+                    span("", " "),
+                    span(&c.command, t.command.clone()),
+                ]
+            },
+            Line::Event(i, e) => {
+                let event = state.items[i].events[e];
+                vec![
+                    span("", "    "),
+                    span(kind_style(event.kind()), format!("{:<11}", event.kind())),
+                    span(&c.package, event.package().unwrap_or("")),
+                    span("", " "),
+                    span(&c.version, event.version().unwrap_or("")),
+                ]
+            },
+        };
+        let selected = state.top + row == state.cursor;
+        draw_spans(out, 0, row + 1, list_width, &spans, selected)?;
+    }
+
+    if side_width > 0 {
+        for row in 1..=list_height{
+            queue!(out, cursor::MoveTo(list_width as u16, row as u16), Print("│"))?;
+        }
+        let side = side_panel(state, events, side_width);
+        for (row, spans) in side.iter().take(list_height).enumerate(){
+            draw_spans(out, list_width + 1, row + 1, side_width, spans, false)?;
+        }
+    }
+
+    let status = match &state.prompt{
+        Some(Prompt::Search) => vec![span("", "search: "), span(&c.bold, state.input.clone())],
+        Some(Prompt::Date) => vec![
+            span("", "date (YYYY, YYYY-MM or YYYY-MM-DD): "), span(&c.bold, state.input.clone())
+        ],
+        None => vec![span(&c.faint, HELP)],
+    };
+    draw_spans(out, 0, h.saturating_sub(1), w, &status, false)?;
+    out.flush()
+}
+
+/// The history of the selected package, newest first, or the details of the selected transaction.
+fn side_panel(state: &State, events: &Events, width: usize) -> Vec<Vec<Span>>{
+    let c = colours();
+    let mut lines = Vec::new();
+    match state.lines.get(state.cursor){
+        Some(Line::Event(i, e)) => {
+            let package = state.items[*i].events[*e].package().unwrap_or("");
+            lines.push(vec![
+                span(&c.bold, "History of "), span(&c.package, package),
+                span(&c.faint, ", newest first"),
+            ]);
+            // newest first, the panel is cut off at the bottom and the recent events matter most
+            for event in events.iter().rev().filter(|e| e.package() == Some(package)){
+                lines.push(vec![
+                    span(&c.date, strftime(event.dt(), date_format())),
+                    span(kind_style(event.kind()), format!(" {:<11}", event.kind())),
+                    span(&c.version, event.version().unwrap_or("")),
+                ]);
+            }
+        },
+        Some(Line::Transaction(i)) => {
+            let t = &state.items[*i].transaction;
+            lines.push(vec![span(&c.bold, "Transaction")]);
+            lines.push(vec![span(&c.date, strftime(t.dt, date_format()))]);
+            let command = t.command.chars().collect::<Vec<_>>();
+            for chunk in command.chunks(width.max(1)){
+                lines.push(vec![span(&c.command, chunk.iter().collect::<String>())]);
+            }
+            lines.push(Vec::new());
+            let lists = [
+                ("installed", &t.install), ("removed", &t.remove),
+                ("upgraded", &t.upgrade), ("downgraded", &t.downgrade),
+            ];
+            for (kind, list) in lists{
+                if list.is_empty() { continue; }
+                lines.push(vec![span(kind_style(kind), format!("{} {}", list.len(), kind))]);
+            }
+            lines.push(vec![span("", format!("{} packages after", t.packages))]);
+            if !t.named.is_empty() {
+                lines.push(Vec::new());
+                lines.push(vec![span(&c.bold, "Named")]);
+                lines.extend(t.named.iter().map(|p| vec![span(&c.package, p.clone())]));
+            }
+            if !t.unnamed.is_empty() {
+                lines.push(Vec::new());
+                lines.push(vec![span(&c.bold, "Came along")]);
+                lines.extend(t.unnamed.iter().map(|p| vec![span(&c.faint, p.clone())]));
+            }
+        },
+        None => lines.push(vec![span(&c.faint, "No transactions match.")]),
+    }
+    lines
+}
+
+fn kind_style(kind: &str) -> &'static str{
+    let c = colours();
+    match kind{
+        "installed" | "install" => &c.install,
+        "removed" | "remove" => &c.remove,
+        "upgraded" | "upgrade" => &c.upgrade,
+        "downgraded" | "downgrade" => &c.downgrade,
+        _ => &c.command,
+    }
+}
+
+/// Prints spans at a position, cut off at `width` characters.
+fn draw_spans(
+    out: &mut Stdout, x: usize, y: usize, width: usize, spans: &[Span], selected: bool
+) -> io::Result<()>{
+    let c = colours();
+    queue!(out, cursor::MoveTo(x as u16, y as u16))?;
+    if selected {
+        queue!(out, SetAttribute(Attribute::Reverse))?;
+    }
+    let mut left = width;
+    for (style, text) in spans{
+        if left == 0 { break; }
+        let text = text.chars().take(left).collect::<String>();
+        left -= text.chars().count();
+        queue!(out, Print(style), Print(text), Print(&c.reset))?;
+        if selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+    }
+    if selected {
+        queue!(out, Print(" ".repeat(left)))?;
+    }
+    queue!(out, SetAttribute(Attribute::Reset))
+}
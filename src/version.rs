@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+/// Compares package versions like pacman's `vercmp`: `[epoch:]version[-release]`.
+pub fn vercmp(a: &str, b: &str) -> Ordering{
+    if a == b { return Ordering::Equal; }
+    let (epoch_a, version_a, release_a) = split_evr(a);
+    let (epoch_b, version_b, release_b) = split_evr(b);
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (release_a, release_b){
+            (Some(a), Some(b)) => rpmvercmp(a, b),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Epoch, version and release, the epoch is 0 when it is missing.
+fn split_evr(evr: &str) -> (&str, &str, Option<&str>){
+    let (epoch, rest) = match evr.split_once(':'){
+        Some((epoch, rest)) if epoch.bytes().all(|b| b.is_ascii_digit()) => (epoch, rest),
+        _ => ("0", evr),
+    };
+    match rest.rsplit_once('-'){
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// The segment by segment comparison of rpm and pacman: runs of digits compare as numbers,
+/// runs of letters as text, and a number is newer than text.
+fn rpmvercmp(a: &str, b: &str) -> Ordering{
+    if a == b { return Ordering::Equal; }
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    loop{
+        let (start_i, start_j) = (i, j);
+        while i < a.len() && !a[i].is_ascii_alphanumeric() { i += 1; }
+        while j < b.len() && !b[j].is_ascii_alphanumeric() { j += 1; }
+        if i >= a.len() || j >= b.len() { break; }
+        // more separators is newer
+        if i - start_i != j - start_j {
+            return (i - start_i).cmp(&(j - start_j));
+        }
+        let numeric = a[i].is_ascii_digit();
+        let same_kind = |c: u8| if numeric { c.is_ascii_digit() } else { c.is_ascii_alphabetic() };
+        let (seg_i, seg_j) = (i, j);
+        while i < a.len() && same_kind(a[i]) { i += 1; }
+        while j < b.len() && same_kind(b[j]) { j += 1; }
+        let (mut one, mut two) = (&a[seg_i..i], &b[seg_j..j]);
+        if two.is_empty() {
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+        if numeric {
+            while one.first() == Some(&b'0') { one = &one[1..]; }
+            while two.first() == Some(&b'0') { two = &two[1..]; }
+            let by_length = one.len().cmp(&two.len());
+            if by_length != Ordering::Equal { return by_length; }
+        }
+        let by_text = one.cmp(two);
+        if by_text != Ordering::Equal { return by_text; }
+    }
+    match (a.get(i), b.get(j)){
+        (None, None) => Ordering::Equal,
+        // a remaining alpha segment never beats an empty one
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use Ordering::*;
+
+    #[test]
+    fn versions_compare_like_pacman(){
+        let cases = [
+            ("1.0", "1.0", Equal),
+            ("1.0", "1.1", Less),
+            ("1.5.1", "1.5", Greater),
+            ("1.5.0", "1.5", Greater),
+            ("1.01", "1.1", Equal),
+            ("1.10", "1.9", Greater),
+            ("1.5b", "1.5", Less),
+            ("1.1alpha1", "1.1", Less),
+            ("1.1alpha1", "1.1beta1", Less),
+            ("1.1rc1", "1.1", Less),
+            ("1.0.a", "1.0.1", Less),
+            ("1.0-1", "1.0-2", Less),
+            ("1.0-2", "1.1-1", Less),
+            ("1.0", "1.0-1", Equal),
+            ("1:1.0", "2.0", Greater),
+            ("0:1.0", "1.0", Equal),
+            ("1:24.0.5-1", "1:24.1.0-1", Less),
+            ("6.2.0.arch1-1", "6.2.0.arch2-1", Less),
+        ];
+        for (a, b, expected) in cases{
+            assert_eq!(vercmp(a, b), expected, "{} {}", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "{} {}", b, a);
+        }
+    }
+}
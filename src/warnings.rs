@@ -0,0 +1,173 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Event, Events, Marker, hours, format_dt, format_hours,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+};
+
+#[derive(Serialize)]
+struct Warning{
+    #[serde(serialize_with = "serialize_dt")]
+    date: DT,
+    kind: &'static str,
+    command: String,
+    detail: String,
+}
+
+impl Tabular for Warning{
+    const HEADER: &'static [&'static str] = &["date", "kind", "command", "detail"];
+
+    fn fields(&self) -> Vec<String>{
+        vec![iso_dt(self.date), self.kind.to_string(), self.command.clone(), self.detail.clone()]
+    }
+}
+
+/// The -S operation flags of a pacman command: whether it refreshes the databases and whether it
+/// upgrades the system. None for other operations.
+pub fn sync_flags(command: &str) -> Option<(bool, bool)>{
+    let (mut sync, mut refresh, mut upgrade) = (false, false, false);
+    for arg in command.split_whitespace().skip(1){
+        match arg{
+            "--" => break,
+            "--sync" => sync = true,
+            "--refresh" => refresh = true,
+            "--sysupgrade" => upgrade = true,
+            _ if arg.starts_with("--") => { },
+            _ if arg.starts_with('-') => {
+                sync |= arg.contains('S');
+                refresh |= arg.contains('y');
+                upgrade |= arg.contains('u');
+            },
+            _ => { },
+        }
+    }
+    sync.then_some((refresh, upgrade))
+}
+
+/// A database sync that no full upgrade followed yet.
+struct Sync<'a>{
+    dt: DT,
+    command: &'a str,
+}
+
+/// Finds partial upgrades: refreshing the databases without upgrading, installing packages after
+/// such a sync, and a sync that took more than `days` to be followed by a full upgrade.
+pub fn warnings(events: Events, now: i64, days: u32, output: Output){
+    let mut warnings = Vec::new();
+    let mut pending: Option<Sync> = None;
+    let mut command: Option<(DT, &str)> = None;
+    // packages of the current transaction installed on top of a pending sync
+    let mut installed: Vec<&str> = Vec::new();
+    let mut flagged = false;
+    let late = |warnings: &mut Vec<Warning>, sync: &Sync, until: Option<DT>|{
+        let gap = until.map_or(now, hours) - hours(sync.dt);
+        if gap < days as i64 * 24 { return; }
+        let detail = match until{
+            Some(until) => format!(
+                "the next full upgrade was {} later, {}", format_hours(gap), format_dt(until)
+            ),
+            None => format!("no full upgrade since, {} ago", format_hours(gap)),
+        };
+        warnings.push(Warning{
+            date: sync.dt, kind: "late upgrade", command: sync.command.to_string(), detail,
+        });
+    };
+    let finish = |warnings: &mut Vec<Warning>, command: Option<(DT, &str)>,
+        installed: &[&str], pending: &Option<Sync>|
+    {
+        let (Some((dt, command)), Some(sync)) = (command, pending) else { return; };
+        if installed.is_empty() { return; }
+        warnings.push(Warning{
+            date: dt,
+            kind: "install after sync",
+            command: command.to_string(),
+            detail: format!(
+                "installed {} on top of the sync of {} without a full upgrade",
+                installed.join(", "), format_dt(sync.dt)
+            ),
+        });
+    };
+    for event in &events{
+        match event{
+            Event::Command(dt, cmd) => {
+                finish(&mut warnings, command, &installed, &pending);
+                installed.clear();
+                command = Some((*dt, cmd));
+                flagged = false;
+                // pacman logs the sync right after the command, but some logs have it before
+                if let Some(sync) = pending.as_mut() {
+                    if sync.command.is_empty() && sync.dt == *dt { sync.command = cmd; }
+                }
+                if let Some((true, false)) = sync_flags(cmd) {
+                    flagged = true;
+                    warnings.push(Warning{
+                        date: *dt,
+                        kind: "sync without upgrade",
+                        command: cmd.clone(),
+                        detail: String::from("refreshed the databases without -u"),
+                    });
+                    if pending.is_none() {
+                        pending = Some(Sync{ dt: *dt, command: cmd });
+                    }
+                }
+            },
+            Event::Marker(dt, _, Marker::Sync) if pending.is_none() => {
+                let command = command.filter(|(at, _)| at == dt).map_or("", |(_, c)| c);
+                pending = Some(Sync{ dt: *dt, command });
+            },
+            Event::Marker(dt, _, Marker::FullUpgrade) => {
+                if let Some(sync) = pending.take() {
+                    late(&mut warnings, &sync, Some(*dt));
+                }
+            },
+            Event::Installed(_, package, _) | Event::Upgraded(_, package, _)
+                if pending.is_some() && !flagged => installed.push(package),
+            _ => { },
+        }
+    }
+    finish(&mut warnings, command, &installed, &pending);
+    if let Some(sync) = &pending {
+        late(&mut warnings, sync, None);
+    }
+    warnings.sort_by_key(|w| w.date);
+    if output.structured() {
+        print_structured(output, &warnings, &warnings);
+        return;
+    }
+
+    let c = colours();
+    for w in &warnings{
+        println!(
+            "{} {}{}{} {}{}{}\n\t{}",
+            format_dt(w.date), c.bold, w.kind, c.reset, c.command, w.command, c.reset, w.detail
+        );
+    }
+    if warnings.is_empty() {
+        println!("No partial upgrades found.");
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn sync_flags_read_short_and_long_options(){
+        let cases = [
+            ("pacman -Sy htop", Some((true, false))),
+            ("pacman -Syu", Some((true, true))),
+            ("pacman -Syyu", Some((true, true))),
+            ("pacman -S firefox", Some((false, false))),
+            ("pacman -S --refresh firefox", Some((true, false))),
+            ("pacman --sync --refresh --sysupgrade", Some((true, true))),
+            ("pacman -S --needed -- -yu", Some((false, false))),
+            ("pacman -R foo", None),
+            ("pacman -Qu", None),
+            ("pacman -U /tmp/foo-1-1-x86_64.pkg.tar.zst", None),
+        ];
+        for (command, expected) in cases{
+            assert_eq!(sync_flags(command), expected, "{}", command);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::{
+    DT, Events, transactions, replay, format_dt,
+    colour::colours,
+    output::{ Output, Tabular, print_structured, serialize_dt, iso_dt },
+};
+
+#[derive(Serialize)]
+struct Why{
+    package: String,
+    #[serde(serialize_with = "serialize_dt")]
+    installed: DT,
+    command: String,
+    // whether the package was named in the command, or came along as a dependency
+    named: bool,
+    // the packages the command named
+    targets: Vec<String>,
+    installed_now: bool,
+}
+
+impl Tabular for Why{
+    const HEADER: &'static [&'static str] = &[
+        "package", "installed", "command", "named", "targets", "installed_now",
+    ];
+
+    fn fields(&self) -> Vec<String>{
+        vec![
+            self.package.clone(),
+            iso_dt(self.installed),
+            self.command.clone(),
+            self.named.to_string(),
+            self.targets.join(" "),
+            self.installed_now.to_string(),
+        ]
+    }
+}
+
+/// Finds the transaction that last installed the package and tells whether it was asked for,
+/// or pulled in by the packages the command did name.
+pub fn why(events: Events, package: String, output: Output){
+    let history = transactions(&events);
+    let found = history.iter().rev()
+        .find(|t| t.install.contains(&package))
+        .map(|t| Why{
+            package: package.clone(),
+            installed: t.dt,
+            command: t.command.clone(),
+            named: t.named.contains(&package),
+            targets: t.named.iter().filter(|p| **p != package).cloned().collect(),
+            installed_now: replay(&events).contains_key(package.as_str()),
+        });
+    if output.structured() {
+        let found = found.into_iter().collect::<Vec<_>>();
+        print_structured(output, &found, &found);
+        return;
+    }
+
+    let c = colours();
+    let Some(why) = found else {
+        println!("{}{}{} was never installed according to the log.", c.package, package, c.reset);
+        return;
+    };
+    println!(
+        "{}{}{} was {}installed{} {} with: {}{}{}",
+        c.package, why.package, c.reset, c.install, c.reset,
+        format_dt(why.installed), c.command, why.command, c.reset
+    );
+    if why.named {
+        println!("\tnamed in the command");
+        if !why.targets.is_empty() {
+            println!("\ttogether with: {}", why.targets.join(", "));
+        }
+    } else if why.targets.is_empty() {
+        println!("\tas a dependency, the command named no packages");
+    } else {
+        println!(
+            "\tas a dependency of: {}{}{}",
+            c.bold, why.targets.join(", "), c.reset
+        );
+    }
+    if !why.installed_now {
+        println!("\t{}removed{} since", c.remove, c.reset);
+    }
+}